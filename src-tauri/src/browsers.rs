@@ -0,0 +1,383 @@
+use crate::{
+    index::with_scan_index,
+    operation::Operation,
+    scan::{run_parallel, scan_root},
+    CANCELLED_MESSAGE,
+};
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Name, base env var, user data dir and whether that dir is the only profile.
+const CHROMIUM_BROWSERS: [(&str, &str, &str, bool); 5] = [
+    ("Chrome", "LOCALAPPDATA", "Google\\Chrome\\User Data", false),
+    ("Edge", "LOCALAPPDATA", "Microsoft\\Edge\\User Data", false),
+    (
+        "Brave",
+        "LOCALAPPDATA",
+        "BraveSoftware\\Brave-Browser\\User Data",
+        false,
+    ),
+    ("Vivaldi", "LOCALAPPDATA", "Vivaldi\\User Data", false),
+    ("Opera", "APPDATA", "Opera Software\\Opera Stable", true),
+];
+
+const CHROMIUM_CACHE_DIRS: [(&str, &str); 4] = [
+    ("Cache", "Cache"),
+    ("Code Cache", "Code Cache"),
+    ("GPUCache", "GPUCache"),
+    (
+        "Service Worker CacheStorage",
+        "Service Worker\\CacheStorage",
+    ),
+];
+
+const FIREFOX_CACHE_DIRS: [(&str, &str); 2] =
+    [("Cache", "cache2"), ("Startup Cache", "startupCache")];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BrowserCache {
+    browser: String,
+    size_bytes: u64,
+    file_count: u64,
+    profiles: Vec<BrowserProfileCache>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BrowserProfileCache {
+    name: String,
+    path: String,
+    size_bytes: u64,
+    file_count: u64,
+    items: Vec<BrowserCacheItem>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BrowserCacheItem {
+    name: String,
+    path: String,
+    size_bytes: u64,
+    file_count: u64,
+}
+
+pub(crate) fn scan_browser_caches_sync(
+    profiles: &[BrowserProfile],
+    threads: usize,
+    force_rescan: bool,
+    op: &Operation,
+) -> Result<Vec<BrowserCache>, String> {
+    let tasks: Vec<(usize, usize)> = profiles
+        .iter()
+        .enumerate()
+        .flat_map(|(index, profile)| (0..profile.caches.len()).map(move |item| (index, item)))
+        .collect();
+    let roots: Vec<PathBuf> = profiles
+        .iter()
+        .flat_map(|profile| profile.caches.iter().map(|(_, path)| path.clone()))
+        .collect();
+    let scans = with_scan_index(op, force_rescan, &roots, |index| {
+        run_parallel(&tasks, threads, |(profile, item)| {
+            let root = &profiles[*profile].caches[*item].1;
+            op.progress.enter(Some("browser_cache"), root);
+            scan_root(root, None, op, index)
+        })
+    });
+    if op.is_cancelled() {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+
+    let mut browsers: Vec<BrowserCache> = Vec::new();
+    let mut scans = scans.into_iter();
+    for profile in profiles {
+        let items: Vec<BrowserCacheItem> = profile
+            .caches
+            .iter()
+            .zip(scans.by_ref())
+            .map(|((name, path), scan)| BrowserCacheItem {
+                name: name.to_string(),
+                path: path.to_string_lossy().to_string(),
+                size_bytes: scan.size_bytes,
+                file_count: scan.file_count,
+            })
+            .collect();
+        let cache = BrowserProfileCache {
+            name: profile.name.clone(),
+            path: profile.dir.to_string_lossy().to_string(),
+            size_bytes: items.iter().map(|item| item.size_bytes).sum(),
+            file_count: items.iter().map(|item| item.file_count).sum(),
+            items,
+        };
+        let position = match browsers
+            .iter()
+            .position(|browser| browser.browser == profile.browser)
+        {
+            Some(position) => position,
+            None => {
+                browsers.push(BrowserCache {
+                    browser: profile.browser.to_string(),
+                    size_bytes: 0,
+                    file_count: 0,
+                    profiles: Vec::new(),
+                });
+                browsers.len() - 1
+            }
+        };
+        let browser = &mut browsers[position];
+        browser.size_bytes += cache.size_bytes;
+        browser.file_count += cache.file_count;
+        browser.profiles.push(cache);
+    }
+    for browser in &mut browsers {
+        browser
+            .profiles
+            .sort_by_key(|profile| Reverse(profile.size_bytes));
+    }
+    browsers.sort_by_key(|browser| Reverse(browser.size_bytes));
+    Ok(browsers)
+}
+
+/// Profiles seen by the last scan, so listings and cleans use the same roots.
+#[derive(Default)]
+pub(crate) struct BrowserProfiles(Mutex<Option<Arc<Vec<BrowserProfile>>>>);
+
+impl BrowserProfiles {
+    pub(crate) fn discover(&self) -> Arc<Vec<BrowserProfile>> {
+        let profiles = Arc::new(discover_browser_profiles());
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = Some(profiles.clone());
+        profiles
+    }
+
+    pub(crate) fn current(&self) -> Arc<Vec<BrowserProfile>> {
+        let cached = self.0.lock().unwrap_or_else(|err| err.into_inner()).clone();
+        cached.unwrap_or_else(|| self.discover())
+    }
+}
+
+pub(crate) struct BrowserProfile {
+    browser: &'static str,
+    name: String,
+    dir: PathBuf,
+    pub(crate) caches: Vec<(&'static str, PathBuf)>,
+}
+
+fn discover_browser_profiles() -> Vec<BrowserProfile> {
+    let local_app_data = env::var("LOCALAPPDATA").ok().map(PathBuf::from);
+    let app_data = env::var("APPDATA").ok().map(PathBuf::from);
+    let mut profiles = Vec::new();
+
+    for (browser, base_var, relative, single_profile) in CHROMIUM_BROWSERS {
+        let Some(base) = env::var(base_var).ok().map(PathBuf::from) else {
+            continue;
+        };
+        let user_data = base.join(relative);
+        // Caches always live under the local app data, even when the
+        // profile itself roams.
+        let cache_root = local_app_data
+            .as_ref()
+            .map_or_else(|| user_data.clone(), |local| local.join(relative));
+        let entries = if single_profile {
+            vec![(None, "Default".to_string())]
+        } else {
+            chromium_profiles(&user_data)
+                .into_iter()
+                .map(|(dir, name)| (Some(dir), name))
+                .collect()
+        };
+        for (dir, name) in entries {
+            let (profile_dir, cache_dir) = match &dir {
+                Some(dir) => (user_data.join(dir), cache_root.join(dir)),
+                None => (user_data.clone(), cache_root.clone()),
+            };
+            let caches = existing_caches(&cache_dir, &CHROMIUM_CACHE_DIRS);
+            if !caches.is_empty() {
+                profiles.push(BrowserProfile {
+                    browser,
+                    name,
+                    dir: profile_dir,
+                    caches,
+                });
+            }
+        }
+    }
+
+    if let (Some(app_data), Some(local)) = (&app_data, &local_app_data) {
+        let roaming = app_data.join("Mozilla").join("Firefox");
+        let local = local.join("Mozilla").join("Firefox");
+        for (name, path, is_relative) in firefox_profiles(&roaming) {
+            // A relative profile keeps its cache in the mirrored local
+            // directory; a custom location keeps it next to the profile.
+            let (profile_dir, cache_dir) = if is_relative {
+                (roaming.join(&path), local.join(&path))
+            } else {
+                (PathBuf::from(&path), PathBuf::from(&path))
+            };
+            let caches = existing_caches(&cache_dir, &FIREFOX_CACHE_DIRS);
+            if !caches.is_empty() {
+                profiles.push(BrowserProfile {
+                    browser: "Firefox",
+                    name,
+                    dir: profile_dir,
+                    caches,
+                });
+            }
+        }
+    }
+
+    profiles
+}
+
+fn existing_caches(dir: &Path, names: &[(&'static str, &str)]) -> Vec<(&'static str, PathBuf)> {
+    names
+        .iter()
+        .map(|(label, relative)| (*label, dir.join(relative)))
+        .filter(|(_, path)| path.is_dir())
+        .collect()
+}
+
+fn chromium_profiles(user_data: &Path) -> Vec<(String, String)> {
+    let mut profiles: Vec<(String, String)> = fs::read_to_string(user_data.join("Local State"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|state| {
+            let cache = state.pointer("/profile/info_cache")?.as_object()?;
+            Some(
+                cache
+                    .iter()
+                    .map(|(dir, info)| {
+                        let name = info.get("name").and_then(|name| name.as_str());
+                        (dir.clone(), name.unwrap_or(dir).to_string())
+                    })
+                    .collect(),
+            )
+        })
+        .unwrap_or_default();
+    if profiles.is_empty() {
+        profiles.push(("Default".to_string(), "Default".to_string()));
+    }
+    if user_data.join("Guest Profile").is_dir() {
+        profiles.push(("Guest Profile".to_string(), "Guest".to_string()));
+    }
+    profiles.sort();
+    profiles
+}
+
+fn firefox_profiles(root: &Path) -> Vec<(String, String, bool)> {
+    let Ok(text) = fs::read_to_string(root.join("profiles.ini")) else {
+        return Vec::new();
+    };
+    let mut profiles = Vec::new();
+    let mut section: Option<HashMap<String, String>> = None;
+    for line in text.lines().map(str::trim).chain(std::iter::once("[]")) {
+        if line.starts_with('[') && line.ends_with(']') {
+            if let Some(values) = section.take() {
+                if let Some(path) = values.get("Path") {
+                    let name = values.get("Name").unwrap_or(path).clone();
+                    let is_relative = values.get("IsRelative").is_none_or(|value| value == "1");
+                    profiles.push((name, path.clone(), is_relative));
+                }
+            }
+            section = line[1..line.len() - 1]
+                .starts_with("Profile")
+                .then(HashMap::new);
+        } else if let (Some(values), Some((key, value))) = (&mut section, line.split_once('=')) {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn firefox_profiles_read_relative_and_custom_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("profiles.ini"),
+            "[General]\nStartWithLastProfile=1\n\n\
+             [Profile0]\nName=default-release\nIsRelative=1\nPath=Profiles/abc.default-release\n\n\
+             [Profile1]\nIsRelative=0\nPath=D:\\Firefox\\work\n\n\
+             [Install308046B0AF4A39CB]\nDefault=Profiles/abc.default-release\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            firefox_profiles(dir.path()),
+            vec![
+                (
+                    "default-release".to_string(),
+                    "Profiles/abc.default-release".to_string(),
+                    true
+                ),
+                (
+                    "D:\\Firefox\\work".to_string(),
+                    "D:\\Firefox\\work".to_string(),
+                    false
+                ),
+            ]
+        );
+        assert!(firefox_profiles(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn chromium_profiles_come_from_local_state_plus_guest() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            chromium_profiles(dir.path()),
+            vec![("Default".to_string(), "Default".to_string())]
+        );
+
+        fs::write(
+            dir.path().join("Local State"),
+            r#"{"profile":{"info_cache":{"Profile 1":{"name":"Work"},"Default":{}}}}"#,
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("Guest Profile")).unwrap();
+        assert_eq!(
+            chromium_profiles(dir.path()),
+            vec![
+                ("Default".to_string(), "Default".to_string()),
+                ("Guest Profile".to_string(), "Guest".to_string()),
+                ("Profile 1".to_string(), "Work".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn categories_use_the_profiles_they_are_given() {
+        let cache = PathBuf::from("profile-cache");
+        let profile = BrowserProfile {
+            browser: "Chrome",
+            name: "Default".to_string(),
+            dir: PathBuf::from("profile"),
+            caches: vec![("Cache", cache.clone())],
+        };
+        let roots = |profiles: &[BrowserProfile]| {
+            crate::build_categories(&HashMap::new(), profiles)
+                .into_iter()
+                .find(|def| def.id == "browser_cache")
+                .unwrap()
+                .roots
+        };
+
+        assert!(roots(&[profile]).contains(&cache));
+        assert!(!roots(&[]).contains(&cache));
+    }
+
+    #[test]
+    fn current_profiles_reuse_the_last_discovery() {
+        let state = BrowserProfiles::default();
+        let discovered = state.discover();
+        assert!(Arc::ptr_eq(&discovered, &state.current()));
+        assert!(!Arc::ptr_eq(&discovered, &state.discover()));
+    }
+}
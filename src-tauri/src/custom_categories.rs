@@ -0,0 +1,246 @@
+use crate::{
+    browsers::BrowserProfile, build_categories, cutoff::FilePredicate, dedup_paths, is_within_root,
+    mounted_volumes, providers::FileProvider, system_drive_mount, CategoryDef, CategoryKind,
+    CategoryOptions,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::ErrorKind,
+    path::{Component, PathBuf},
+};
+use tauri::{AppHandle, Manager};
+
+const CUSTOM_CATEGORIES_FILE: &str = "categories.json";
+
+#[derive(Default, Deserialize)]
+struct CustomCategoriesConfig {
+    categories: Vec<CustomCategory>,
+}
+
+/// `roots` may start with `~` and use `%NAME%` environment variables.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CustomCategory {
+    id: String,
+    title: String,
+    #[serde(default)]
+    description: String,
+    roots: Vec<String>,
+    #[serde(default)]
+    predicates: Vec<FilePredicate>,
+    #[serde(default)]
+    cleanup_dirs: bool,
+}
+
+pub(crate) fn load_categories(
+    app: &AppHandle,
+    options: &HashMap<String, CategoryOptions>,
+    browser_profiles: &[BrowserProfile],
+) -> Result<Vec<CategoryDef>, String> {
+    let path = app
+        .path()
+        .app_config_dir()
+        .map(|dir| dir.join(CUSTOM_CATEGORIES_FILE))
+        .map_err(|err| err.to_string())?;
+    let config: CustomCategoriesConfig = match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|err| format!("Invalid {}: {}", path.display(), err))?,
+        Err(err) if err.kind() == ErrorKind::NotFound => CustomCategoriesConfig::default(),
+        Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+    };
+    let mut categories = build_categories(options, browser_profiles);
+    for custom in config.categories {
+        let def = custom_category(custom, &categories)
+            .map_err(|err| format!("Invalid {}: {}", path.display(), err))?;
+        categories.push(def);
+    }
+    Ok(categories)
+}
+
+fn custom_category(
+    custom: CustomCategory,
+    existing: &[CategoryDef],
+) -> Result<CategoryDef, String> {
+    let id = custom.id.trim().to_string();
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+    {
+        return Err(format!(
+            "category id \"{}\" must be letters, digits, _ or -",
+            custom.id
+        ));
+    }
+    if existing.iter().any(|def| def.id == id) {
+        return Err(format!("duplicate category id {}", id));
+    }
+    if custom.title.trim().is_empty() {
+        return Err(format!("category {} has no title", id));
+    }
+    if custom.roots.is_empty() {
+        return Err(format!("category {} has no roots", id));
+    }
+    let protected = protected_dirs();
+    let mut roots = Vec::new();
+    for root in &custom.roots {
+        let path = expand_root(root).map_err(|err| format!("category {}: {}", id, err))?;
+        if !path.is_absolute() {
+            return Err(format!("category {}: root {} is not absolute", id, root));
+        }
+        if path
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            return Err(format!("category {}: root {} contains ..", id, root));
+        }
+        if path.parent().is_none() {
+            return Err(format!("category {}: root {} is a whole volume", id, root));
+        }
+        if let Some(dir) = protected.iter().find(|dir| is_within_root(&path, dir)) {
+            return Err(format!(
+                "category {}: root {} would include {}, which is never cleaned",
+                id,
+                root,
+                dir.display()
+            ));
+        }
+        roots.push(path);
+    }
+    Ok(CategoryDef {
+        id,
+        title: custom.title,
+        description: custom.description,
+        kind: if custom.predicates.is_empty() {
+            CategoryKind::Standard
+        } else {
+            CategoryKind::Filtered(custom.predicates)
+        },
+        retention_days: None,
+        roots: dedup_paths(roots),
+        cleanup_dirs: custom.cleanup_dirs,
+        provider: &FileProvider,
+    })
+}
+
+pub(crate) fn protected_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = [
+        "USERPROFILE",
+        "HOME",
+        "SystemRoot",
+        "ProgramFiles",
+        "ProgramFiles(x86)",
+        "ProgramW6432",
+        "ProgramData",
+    ]
+    .iter()
+    .filter_map(|name| env::var(name).ok())
+    .filter(|value| !value.trim().is_empty())
+    .map(PathBuf::from)
+    .collect();
+    dirs.extend(
+        mounted_volumes()
+            .into_iter()
+            .chain(std::iter::once(system_drive_mount()))
+            .map(|volume| volume.join("Users")),
+    );
+    dirs
+}
+
+fn expand_root(root: &str) -> Result<PathBuf, String> {
+    expand_root_with(root, |name| env::var(name).ok())
+}
+
+fn expand_root_with(root: &str, var: impl Fn(&str) -> Option<String>) -> Result<PathBuf, String> {
+    let mut output = String::new();
+    let mut rest = root.trim();
+    if let Some(tail) = rest.strip_prefix('~') {
+        if tail.is_empty() || tail.starts_with(['/', '\\']) {
+            let home = var("USERPROFILE")
+                .or_else(|| var("HOME"))
+                .ok_or_else(|| format!("cannot expand ~ in {}", root))?;
+            output.push_str(&home);
+            rest = tail;
+        }
+    }
+    while let Some(start) = rest.find('%') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('%')
+            .ok_or_else(|| format!("unterminated % in {}", root))?;
+        let name = &after[..end];
+        let value = var(name)
+            .ok_or_else(|| format!("unknown environment variable {} in {}", name, root))?;
+        output.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(PathBuf::from(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(name: &str) -> Option<String> {
+        match name {
+            "USERPROFILE" => Some("C:\\Users\\me".to_string()),
+            "LOCALAPPDATA" => Some("C:\\Users\\me\\AppData\\Local".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn roots_expand_home_and_percent_variables() {
+        let expand = |root: &str| expand_root_with(root, vars);
+        assert_eq!(
+            expand(" ~\\Videos ").unwrap(),
+            PathBuf::from("C:\\Users\\me\\Videos")
+        );
+        assert_eq!(
+            expand("%LOCALAPPDATA%\\Temp").unwrap(),
+            PathBuf::from("C:\\Users\\me\\AppData\\Local\\Temp")
+        );
+        assert_eq!(expand("~other").unwrap(), PathBuf::from("~other"));
+        assert!(expand("%MISSING%\\x").is_err());
+        assert!(expand("%LOCALAPPDATA\\x").is_err());
+
+        let home_only = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+        assert_eq!(
+            expand_root_with("~/cache", home_only).unwrap(),
+            PathBuf::from("/home/me/cache")
+        );
+        assert!(expand_root_with("~", |_| None).is_err());
+    }
+
+    #[test]
+    fn custom_categories_are_validated_before_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("cache").to_string_lossy().to_string();
+        let custom = |id: &str, roots: Vec<String>| CustomCategory {
+            id: id.to_string(),
+            title: "Title".to_string(),
+            description: String::new(),
+            roots,
+            predicates: vec![FilePredicate::SizeAbove { bytes: 1 }],
+            cleanup_dirs: false,
+        };
+        let existing = [CategoryDef::for_roots("taken", Vec::new())];
+        let check =
+            |id: &str, root: &str| custom_category(custom(id, vec![root.to_string()]), &existing);
+
+        let def = check(" mine ", &root).unwrap();
+        assert_eq!(def.id, "mine");
+        assert!(matches!(def.kind, CategoryKind::Filtered(_)));
+        assert!(check("bad id", &root).is_err());
+        assert!(check("taken", &root).is_err());
+        assert!(check("rel", "relative\\path").is_err());
+        assert!(check("up", &format!("{}/../x", root)).is_err());
+        let volume = dir.path().ancestors().last().unwrap().to_string_lossy();
+        assert!(check("volume", &volume).is_err());
+        assert!(custom_category(custom("none", Vec::new()), &existing).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use serde::Deserialize;
+use std::{
+    fs,
+    time::{Duration, SystemTime},
+};
+
+/// "Newest" is the latest of the created, modified and accessed times.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum FilePredicate {
+    ModifiedOlderThan { days: u64 },
+    AccessedOlderThan { days: u64 },
+    CreatedOlderThan { days: u64 },
+    NewestOlderThan { days: u64 },
+    SizeAbove { bytes: u64 },
+    SizeBelow { bytes: u64 },
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct FileStat {
+    pub(crate) size: u64,
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) accessed: Option<SystemTime>,
+    pub(crate) created: Option<SystemTime>,
+}
+
+impl FileStat {
+    pub(crate) fn of(metadata: &fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            created: metadata.created().ok(),
+        }
+    }
+}
+
+/// A time a file does not report never counts as old.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Cutoff {
+    modified_before: Option<SystemTime>,
+    accessed_before: Option<SystemTime>,
+    created_before: Option<SystemTime>,
+    newest_before: Option<SystemTime>,
+    larger_than: Option<u64>,
+    smaller_than: Option<u64>,
+}
+
+impl Cutoff {
+    pub(crate) fn new(predicates: &[FilePredicate]) -> Self {
+        fn earliest(bound: &mut Option<SystemTime>, days: u64) {
+            let time = days_ago(days);
+            *bound = Some(bound.map_or(time, |bound| bound.min(time)));
+        }
+        let mut cutoff = Self::default();
+        for predicate in predicates {
+            match *predicate {
+                FilePredicate::ModifiedOlderThan { days } => {
+                    earliest(&mut cutoff.modified_before, days)
+                }
+                FilePredicate::AccessedOlderThan { days } => {
+                    earliest(&mut cutoff.accessed_before, days)
+                }
+                FilePredicate::CreatedOlderThan { days } => {
+                    earliest(&mut cutoff.created_before, days)
+                }
+                FilePredicate::NewestOlderThan { days } => {
+                    earliest(&mut cutoff.newest_before, days)
+                }
+                FilePredicate::SizeAbove { bytes } => {
+                    cutoff.larger_than = Some(cutoff.larger_than.map_or(bytes, |b| b.max(bytes)))
+                }
+                FilePredicate::SizeBelow { bytes } => {
+                    cutoff.smaller_than = Some(cutoff.smaller_than.map_or(bytes, |b| b.min(bytes)))
+                }
+            }
+        }
+        cutoff
+    }
+
+    pub(crate) fn uses_file_times(&self) -> bool {
+        self.accessed_before.is_some()
+            || self.created_before.is_some()
+            || self.newest_before.is_some()
+    }
+
+    pub(crate) fn matches(&self, file: &FileStat) -> bool {
+        let before = |time: Option<SystemTime>, bound: Option<SystemTime>| {
+            bound.is_none_or(|bound| time.is_some_and(|time| time < bound))
+        };
+        let newest = [file.modified, file.accessed, file.created]
+            .into_iter()
+            .flatten()
+            .max();
+        before(file.modified, self.modified_before)
+            && before(file.accessed, self.accessed_before)
+            && before(file.created, self.created_before)
+            && before(newest, self.newest_before)
+            && self.larger_than.is_none_or(|bound| file.size > bound)
+            && self.smaller_than.is_none_or(|bound| file.size < bound)
+    }
+}
+
+pub(crate) fn days_ago(days: u64) -> SystemTime {
+    SystemTime::now()
+        .checked_sub(Duration::from_secs(days.saturating_mul(86_400)))
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(size: u64, modified: u64, accessed: u64, created: Option<u64>) -> FileStat {
+        FileStat {
+            size,
+            modified: Some(days_ago(modified)),
+            accessed: Some(days_ago(accessed)),
+            created: created.map(days_ago),
+        }
+    }
+
+    #[test]
+    fn predicates_must_all_hold() {
+        let cutoff = Cutoff::new(&[
+            FilePredicate::ModifiedOlderThan { days: 30 },
+            FilePredicate::SizeAbove { bytes: 100 },
+            FilePredicate::SizeBelow { bytes: 1000 },
+        ]);
+        assert!(cutoff.matches(&stat(500, 40, 0, Some(40))));
+        assert!(!cutoff.matches(&stat(500, 20, 0, Some(40))));
+        assert!(!cutoff.matches(&stat(100, 40, 0, Some(40))));
+        assert!(!cutoff.matches(&stat(1000, 40, 0, Some(40))));
+    }
+
+    #[test]
+    fn newest_time_and_missing_times_are_never_old() {
+        let newest = Cutoff::new(&[FilePredicate::NewestOlderThan { days: 30 }]);
+        assert!(newest.matches(&stat(1, 60, 45, Some(90))));
+        assert!(!newest.matches(&stat(1, 60, 2, Some(90))));
+
+        let created = Cutoff::new(&[FilePredicate::CreatedOlderThan { days: 30 }]);
+        assert!(!created.matches(&stat(1, 60, 60, None)));
+        assert!(created.uses_file_times());
+        assert!(!Cutoff::new(&[FilePredicate::ModifiedOlderThan { days: 1 }]).uses_file_times());
+    }
+
+    #[test]
+    fn repeated_predicates_keep_the_strictest_bound() {
+        let cutoff = Cutoff::new(&[
+            FilePredicate::AccessedOlderThan { days: 10 },
+            FilePredicate::AccessedOlderThan { days: 60 },
+            FilePredicate::SizeAbove { bytes: 10 },
+            FilePredicate::SizeAbove { bytes: 50 },
+        ]);
+        assert!(!cutoff.matches(&stat(100, 0, 30, None)));
+        assert!(!cutoff.matches(&stat(20, 0, 90, None)));
+        assert!(cutoff.matches(&stat(100, 0, 90, None)));
+        assert_eq!(days_ago(u64::MAX), SystemTime::UNIX_EPOCH);
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{
+    delete_selected_path,
+    file_usage::{file_id, FileId, FileUsage},
+    index::{DirRecord, IndexSession},
+    mounted_volumes, normalize_path, normalize_path_str,
+    operation::Operation,
+    scan::{run_parallel, walk_root, ScanVisitor},
+    to_unix_ms, volume_of, CleanupError, CleanupResult, CANCELLED_MESSAGE,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    env,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+pub(crate) const DEFAULT_DUPLICATE_MIN_MB: u64 = 1;
+const DUPLICATE_PARTIAL_BYTES: u64 = 64 * 1024;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DuplicateGroup {
+    size_bytes: u64,
+    hash: String,
+    reclaimable_bytes: u64,
+    keep_path: String,
+    files: Vec<DuplicateFile>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DuplicateFile {
+    path: String,
+    modified_ms: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DuplicateSelection {
+    keep: String,
+    remove: Vec<String>,
+}
+
+pub(crate) fn default_duplicate_roots() -> Vec<PathBuf> {
+    let Some(user_profile) = env::var("USERPROFILE").ok().map(PathBuf::from) else {
+        return Vec::new();
+    };
+    [
+        "Downloads",
+        "Desktop",
+        "Documents",
+        "Videos",
+        "Pictures",
+        "Music",
+    ]
+    .iter()
+    .map(|name| user_profile.join(name))
+    .collect()
+}
+
+/// Same size, then same partial hash, then same full hash.
+pub(crate) fn find_duplicates_sync(
+    roots: Vec<PathBuf>,
+    min_size_bytes: u64,
+    threads: usize,
+    op: &Operation,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let index = IndexSession::detached();
+    let walks = run_parallel(&roots, threads, |root| {
+        let mut files = SameSizeFiles {
+            min_size_bytes,
+            by_size: HashMap::new(),
+        };
+        op.progress.enter(None, root);
+        walk_root(root, usize::MAX, &mut files, op, &index);
+        files.by_size
+    });
+    let mut by_size: HashMap<u64, Vec<DuplicateCandidate>> = HashMap::new();
+    for walk in walks {
+        for (size, files) in walk {
+            by_size.entry(size).or_default().extend(files);
+        }
+    }
+    let candidates: Vec<(u64, DuplicateCandidate)> = by_size
+        .into_iter()
+        .flat_map(|(size, files)| {
+            let mut seen = HashSet::new();
+            let files: Vec<DuplicateCandidate> = files
+                .into_iter()
+                .filter(|file| seen.insert(file.identity_key()))
+                .collect();
+            let keep = files.len() > 1;
+            files
+                .into_iter()
+                .filter(move |_| keep)
+                .map(move |file| (size, file))
+        })
+        .collect();
+
+    let partial = group_by_hash(candidates, threads, op, |size| {
+        (size > DUPLICATE_PARTIAL_BYTES * 2).then_some(DUPLICATE_PARTIAL_BYTES)
+    });
+    let (full_done, needs_full): (Vec<_>, Vec<_>) = partial
+        .into_iter()
+        .partition(|(size, _, _)| *size <= DUPLICATE_PARTIAL_BYTES * 2);
+    let needs_full = needs_full
+        .into_iter()
+        .flat_map(|(size, _, files)| files.into_iter().map(move |file| (size, file)))
+        .collect();
+    let mut groups = full_done;
+    groups.extend(group_by_hash(needs_full, threads, op, |_| None));
+    if op.is_cancelled() {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+
+    let mut groups: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .map(|(size, hash, mut files)| {
+            files.sort_by_cached_key(|file| file.keep_rank());
+            DuplicateGroup {
+                size_bytes: size,
+                hash: hash.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                reclaimable_bytes: size.saturating_mul(files.len() as u64 - 1),
+                keep_path: files[0].path.to_string_lossy().to_string(),
+                files: files
+                    .into_iter()
+                    .map(|file| DuplicateFile {
+                        path: file.path.to_string_lossy().to_string(),
+                        modified_ms: file.modified_ms,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    groups.sort_by_key(|group| Reverse(group.reclaimable_bytes));
+    Ok(groups)
+}
+
+fn group_by_hash(
+    candidates: Vec<(u64, DuplicateCandidate)>,
+    threads: usize,
+    op: &Operation,
+    limit: impl Fn(u64) -> Option<u64> + Sync,
+) -> Vec<(u64, [u8; 32], Vec<DuplicateCandidate>)> {
+    let hashes = run_parallel(&candidates, threads, |(size, file)| {
+        if op.is_cancelled() {
+            return None;
+        }
+        op.progress.enter(None, &file.path);
+        hash_file(&file.path, limit(*size)).ok()
+    });
+    let mut groups: HashMap<(u64, [u8; 32]), Vec<DuplicateCandidate>> = HashMap::new();
+    for ((size, file), hash) in candidates.into_iter().zip(hashes) {
+        if let Some(hash) = hash {
+            groups.entry((size, hash)).or_default().push(file);
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|((size, hash), files)| (size, hash, files))
+        .collect()
+}
+
+fn hash_file(path: &Path, limit: Option<u64>) -> std::io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    match limit {
+        Some(limit) => {
+            let len = file.metadata()?.len();
+            let mut head = Vec::new();
+            (&mut file).take(limit).read_to_end(&mut head)?;
+            hasher.update(&head);
+            file.seek(SeekFrom::Start(len.saturating_sub(limit)))?;
+            let mut tail = Vec::new();
+            file.take(limit).read_to_end(&mut tail)?;
+            hasher.update(&tail);
+        }
+        None => loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        },
+    }
+    Ok(hasher.finalize().into())
+}
+
+pub(crate) fn clean_duplicates_sync(
+    groups: Vec<DuplicateSelection>,
+    op: &Operation,
+) -> CleanupResult {
+    let volumes = mounted_volumes();
+    let mut deleted_bytes: u64 = 0;
+    let mut deleted_count: u64 = 0;
+    let mut failed = Vec::new();
+
+    for group in groups {
+        if op.is_cancelled() {
+            break;
+        }
+        let keep = Path::new(&group.keep);
+        let keep_hash = match hash_file(keep, None) {
+            Ok(hash) => hash,
+            Err(err) => {
+                failed.extend(group.remove.iter().map(|path| CleanupError {
+                    path: path.clone(),
+                    message: format!("Kept copy is unreadable: {}", err),
+                }));
+                continue;
+            }
+        };
+        let Some(keep_id) = file_id(keep) else {
+            failed.extend(group.remove.iter().map(|path| CleanupError {
+                path: path.clone(),
+                message: "Cannot identify the kept copy.".to_string(),
+            }));
+            continue;
+        };
+        let keep_key = normalize_path(keep);
+        let mut seen = HashSet::new();
+        for path_str in &group.remove {
+            if op.is_cancelled() {
+                break;
+            }
+            let normalized = normalize_path_str(path_str);
+            if !seen.insert(normalized.clone()) {
+                continue;
+            }
+            if normalized == keep_key {
+                failed.push(CleanupError {
+                    path: path_str.clone(),
+                    message: "Refusing to delete the copy marked to keep.".to_string(),
+                });
+                continue;
+            }
+            let path = Path::new(path_str);
+            let Some(root) = volume_of(&volumes, path) else {
+                failed.push(CleanupError {
+                    path: path_str.clone(),
+                    message: "Path is not on a mounted volume.".to_string(),
+                });
+                continue;
+            };
+            op.progress.enter(None, path);
+            match hash_file(path, None) {
+                Ok(hash) if hash == keep_hash => {}
+                Ok(_) => {
+                    failed.push(CleanupError {
+                        path: path_str.clone(),
+                        message: "File no longer matches the kept copy.".to_string(),
+                    });
+                    continue;
+                }
+                Err(err) => {
+                    failed.push(CleanupError {
+                        path: path_str.clone(),
+                        message: err.to_string(),
+                    });
+                    continue;
+                }
+            }
+            match file_id(path) {
+                Some(id) if id != keep_id => {}
+                Some(_) => {
+                    failed.push(CleanupError {
+                        path: path_str.clone(),
+                        message: "Path is the same file as the copy marked to keep.".to_string(),
+                    });
+                    continue;
+                }
+                None => {
+                    failed.push(CleanupError {
+                        path: path_str.clone(),
+                        message: "Cannot identify the file.".to_string(),
+                    });
+                    continue;
+                }
+            }
+            delete_selected_path(
+                path,
+                root,
+                &mut deleted_bytes,
+                &mut deleted_count,
+                &mut failed,
+                op,
+            );
+        }
+    }
+
+    CleanupResult {
+        deleted_bytes,
+        deleted_count,
+        failed,
+        cancelled: op.is_cancelled(),
+    }
+}
+
+struct SameSizeFiles {
+    min_size_bytes: u64,
+    by_size: HashMap<u64, Vec<DuplicateCandidate>>,
+}
+
+struct DuplicateCandidate {
+    path: PathBuf,
+    modified_ms: Option<i64>,
+    file_id: Option<FileId>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Identity {
+    File(FileId),
+    Path(String),
+}
+
+impl DuplicateCandidate {
+    fn identity_key(&self) -> Identity {
+        match self.file_id {
+            Some(id) => Identity::File(id),
+            None => Identity::Path(normalize_path(&self.path)),
+        }
+    }
+
+    /// Prefers files outside download and temp folders, then the oldest.
+    fn keep_rank(&self) -> (bool, i64, usize) {
+        let lower = normalize_path(&self.path);
+        let transient = lower
+            .split('\\')
+            .any(|part| matches!(part, "downloads" | "desktop" | "temp" | "tmp" | "cache"));
+        (transient, self.modified_ms.unwrap_or(i64::MAX), lower.len())
+    }
+}
+
+impl ScanVisitor for SameSizeFiles {
+    fn visit_file(&mut self, path: &Path, metadata: &fs::Metadata, usage: &FileUsage) {
+        if usage.size_bytes < self.min_size_bytes {
+            return;
+        }
+        self.by_size
+            .entry(usage.size_bytes)
+            .or_default()
+            .push(DuplicateCandidate {
+                path: path.to_path_buf(),
+                modified_ms: metadata.modified().ok().and_then(to_unix_ms),
+                file_id: usage
+                    .linked
+                    .map(|identity| identity.id)
+                    .or_else(|| file_id(path)),
+            });
+    }
+
+    fn needs_files(&self, _dir: &Path) -> bool {
+        true
+    }
+
+    fn visit_record(&mut self, _dir: &Path, _record: &DirRecord) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_need_equal_content_not_just_equal_size_or_head_and_tail() {
+        let root = tempfile::tempdir().unwrap();
+        let size = DUPLICATE_PARTIAL_BYTES as usize * 3;
+        let original = vec![7u8; size];
+        let mut middle_differs = original.clone();
+        middle_differs[size / 2] = 8;
+        fs::write(root.path().join("a.bin"), &original).unwrap();
+        fs::write(root.path().join("a-copy.bin"), &original).unwrap();
+        fs::hard_link(root.path().join("a.bin"), root.path().join("a-link.bin")).unwrap();
+        fs::write(root.path().join("b.bin"), &middle_differs).unwrap();
+        fs::write(root.path().join("small.txt"), b"same").unwrap();
+        fs::write(root.path().join("small-copy.txt"), b"same").unwrap();
+        fs::write(root.path().join("other.txt"), b"diff").unwrap();
+
+        let op = Operation::detached("test");
+        let groups = find_duplicates_sync(vec![root.path().to_path_buf()], 1, 2, &op).unwrap();
+        let summary: Vec<(u64, u64, usize)> = groups
+            .iter()
+            .map(|group| (group.size_bytes, group.reclaimable_bytes, group.files.len()))
+            .collect();
+        assert_eq!(summary, [(size as u64, size as u64, 2), (4, 4, 2)]);
+        let big: Vec<&str> = groups[0]
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert!(big.iter().all(|path| !path.ends_with("b.bin")));
+        assert_eq!(groups[0].keep_path, groups[0].files[0].path);
+    }
+
+    #[test]
+    fn keeps_the_copy_outside_download_folders_before_the_oldest() {
+        let candidate = |path: &str, modified_ms| DuplicateCandidate {
+            path: PathBuf::from(path),
+            modified_ms: Some(modified_ms),
+            file_id: None,
+        };
+        let mut files = [
+            candidate("C:\\Users\\me\\Downloads\\setup.exe", 1),
+            candidate("C:\\Users\\me\\Documents\\setup.exe", 3),
+            candidate("C:\\Users\\me\\Documents\\Old\\setup.exe", 2),
+        ];
+        files.sort_by_cached_key(DuplicateCandidate::keep_rank);
+        let order: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(
+            order,
+            [
+                Path::new("C:\\Users\\me\\Documents\\Old\\setup.exe"),
+                Path::new("C:\\Users\\me\\Documents\\setup.exe"),
+                Path::new("C:\\Users\\me\\Downloads\\setup.exe"),
+            ]
+        );
+    }
+
+    #[test]
+    fn clean_refuses_the_kept_copy_and_changed_files() {
+        let root = tempfile::tempdir().unwrap();
+        let keep = root.path().join("keep.bin");
+        let copy = root.path().join("copy.bin");
+        let changed = root.path().join("changed.bin");
+        fs::write(&keep, [1; 64]).unwrap();
+        fs::write(&copy, [1; 64]).unwrap();
+        fs::write(&changed, [2; 64]).unwrap();
+        let text = |path: &Path| path.to_string_lossy().to_string();
+
+        let op = Operation::detached("test");
+        let result = clean_duplicates_sync(
+            vec![DuplicateSelection {
+                keep: text(&keep),
+                remove: vec![text(&keep), text(&changed), text(&copy)],
+            }],
+            &op,
+        );
+        assert_eq!((result.deleted_count, result.deleted_bytes), (1, 64));
+        let failed: Vec<&str> = result
+            .failed
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect();
+        assert_eq!(
+            failed,
+            [
+                "Refusing to delete the copy marked to keep.",
+                "File no longer matches the kept copy.",
+            ]
+        );
+        assert!(keep.exists() && changed.exists() && !copy.exists());
+    }
+}
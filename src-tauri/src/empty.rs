@@ -0,0 +1,351 @@
+use crate::{
+    custom_categories::protected_dirs, is_within_root, mounted_volumes, normalize_path,
+    normalize_path_str, operation::Operation, path_eq_ignore_case, walk_failure, CleanupError,
+    CleanupResult, CANCELLED_MESSAGE,
+};
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EmptyItems {
+    empty_dirs: Vec<EmptyDir>,
+    zero_byte_files: Vec<String>,
+    inaccessible_count: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EmptyDir {
+    path: String,
+    dir_count: u64,
+}
+
+/// Folders a root may not take in: protected folders and whole volumes.
+pub(crate) fn guarded_dirs() -> Vec<PathBuf> {
+    let mut dirs = protected_dirs();
+    dirs.extend(mounted_volumes());
+    dirs
+}
+
+fn check_root(root: &Path, guarded: &[PathBuf]) -> Result<(), String> {
+    if root.parent().is_none() {
+        return Err("Refusing to use a whole volume as the root.".to_string());
+    }
+    match guarded.iter().find(|dir| is_within_root(root, dir)) {
+        Some(dir) => Err(format!(
+            "Root would include {}, which is never cleaned.",
+            dir.display()
+        )),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn find_empty_items_sync(
+    root: &Path,
+    guarded: &[PathBuf],
+    op: &Operation,
+) -> Result<EmptyItems, String> {
+    if !root.is_dir() {
+        return Err("Path is not a directory.".to_string());
+    }
+    check_root(root, guarded)?;
+    op.progress.enter(None, root);
+    // Per directory: whether it holds anything and how many empty folders sit
+    // below it. Contents arrive first, so a directory is settled when yielded.
+    let mut dirs: HashMap<PathBuf, (bool, u64)> = HashMap::new();
+    let mut empty: Vec<(PathBuf, u64)> = Vec::new();
+    let mut zero_byte_files = Vec::new();
+    let mut inaccessible_count = 0;
+    let mark_used = |dirs: &mut HashMap<PathBuf, (bool, u64)>, path: &Path| {
+        if let Some(parent) = path.parent() {
+            dirs.entry(parent.to_path_buf()).or_default().0 = true;
+        }
+    };
+
+    for entry in WalkDir::new(root).follow_links(false).contents_first(true) {
+        if op.is_cancelled() {
+            return Err(CANCELLED_MESSAGE.to_string());
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                inaccessible_count += 1;
+                if let Some(path) = err.path() {
+                    dirs.entry(path.to_path_buf()).or_default().0 = true;
+                    mark_used(&mut dirs, path);
+                }
+                continue;
+            }
+        };
+        let path = entry.path();
+        if !entry.file_type().is_dir() {
+            mark_used(&mut dirs, path);
+            if entry.file_type().is_file() {
+                match entry.metadata() {
+                    Ok(metadata) => {
+                        op.progress.add_file(path, metadata.len());
+                        if metadata.len() == 0 {
+                            zero_byte_files.push(path.to_string_lossy().to_string());
+                        }
+                    }
+                    Err(_) => inaccessible_count += 1,
+                }
+            }
+            continue;
+        }
+        let (used, nested) = dirs.remove(path).unwrap_or_default();
+        if used {
+            mark_used(&mut dirs, path);
+            continue;
+        }
+        if entry.depth() == 0 {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            dirs.entry(parent.to_path_buf()).or_default().1 += nested + 1;
+        }
+        empty.push((path.to_path_buf(), nested + 1));
+    }
+
+    // Keep only the top of each empty tree; the folders below it go with it.
+    let empty_keys: HashSet<String> = empty.iter().map(|(path, _)| normalize_path(path)).collect();
+    let empty_dirs = empty
+        .into_iter()
+        .filter(|(path, _)| {
+            path.parent()
+                .is_none_or(|parent| !empty_keys.contains(&normalize_path(parent)))
+        })
+        .map(|(path, dir_count)| EmptyDir {
+            path: path.to_string_lossy().to_string(),
+            dir_count,
+        })
+        .collect();
+
+    Ok(EmptyItems {
+        empty_dirs,
+        zero_byte_files,
+        inaccessible_count,
+    })
+}
+
+pub(crate) fn clean_empty_items_sync(
+    root: &Path,
+    paths: Vec<String>,
+    guarded: &[PathBuf],
+    op: &Operation,
+) -> CleanupResult {
+    let mut deleted_count: u64 = 0;
+    let mut failed = Vec::new();
+    if let Err(message) = check_root(root, guarded) {
+        failed.extend(paths.into_iter().map(|path| CleanupError {
+            path,
+            message: message.clone(),
+        }));
+        return CleanupResult {
+            deleted_bytes: 0,
+            deleted_count,
+            failed,
+            cancelled: false,
+        };
+    }
+    let mut seen = HashSet::new();
+    let mut targets: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| seen.insert(normalize_path_str(path)))
+        .map(PathBuf::from)
+        .collect();
+    targets.sort_by_key(|path| Reverse(path.components().count()));
+
+    for path in targets {
+        if op.is_cancelled() {
+            break;
+        }
+        let path_str = path.to_string_lossy().to_string();
+        op.progress.enter(None, &path);
+        if !is_within_root(root, &path) {
+            failed.push(CleanupError {
+                path: path_str,
+                message: "Path is outside scan scope.".to_string(),
+            });
+            continue;
+        }
+        if path_eq_ignore_case(&path, root) {
+            failed.push(CleanupError {
+                path: path_str,
+                message: "Refusing to delete the scan root.".to_string(),
+            });
+            continue;
+        }
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                failed.push(CleanupError {
+                    path: path_str,
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        if metadata.is_dir() {
+            remove_empty_tree(&path, &mut deleted_count, &mut failed, op);
+            continue;
+        }
+        if !metadata.is_file() || metadata.len() != 0 {
+            failed.push(CleanupError {
+                path: path_str,
+                message: "File is no longer empty.".to_string(),
+            });
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                deleted_count += 1;
+                op.progress.add_file(&path, 0);
+            }
+            Err(err) => failed.push(CleanupError {
+                path: path_str,
+                message: err.to_string(),
+            }),
+        }
+    }
+
+    CleanupResult {
+        deleted_bytes: 0,
+        deleted_count,
+        failed,
+        cancelled: op.is_cancelled(),
+    }
+}
+
+/// Only the deepest blocker is reported; the folders above it stay silently.
+fn remove_empty_tree(
+    dir: &Path,
+    deleted_count: &mut u64,
+    failed: &mut Vec<CleanupError>,
+    op: &Operation,
+) {
+    let mut kept: HashSet<PathBuf> = HashSet::new();
+    let keep_ancestors = |kept: &mut HashSet<PathBuf>, path: &Path| {
+        for ancestor in path.ancestors().skip(1) {
+            if !ancestor.starts_with(dir) || !kept.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+    };
+    for entry in WalkDir::new(dir).follow_links(false).contents_first(true) {
+        if op.is_cancelled() {
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                let failure = walk_failure(&err, dir);
+                let path = PathBuf::from(&failure.path);
+                kept.insert(path.clone());
+                keep_ancestors(&mut kept, &path);
+                failed.push(failure);
+                continue;
+            }
+        };
+        let path = entry.path();
+        if !entry.file_type().is_dir() {
+            keep_ancestors(&mut kept, path);
+            failed.push(CleanupError {
+                path: path.to_string_lossy().to_string(),
+                message: "Directory is no longer empty.".to_string(),
+            });
+            continue;
+        }
+        if kept.contains(path) {
+            continue;
+        }
+        match fs::remove_dir(path) {
+            Ok(()) => *deleted_count += 1,
+            Err(err) => {
+                keep_ancestors(&mut kept, path);
+                failed.push(CleanupError {
+                    path: path.to_string_lossy().to_string(),
+                    message: err.to_string(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_top_of_each_empty_tree_and_zero_byte_files() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("a").join("b").join("c")).unwrap();
+        fs::create_dir_all(root.path().join("a").join("d")).unwrap();
+        fs::create_dir(root.path().join("full")).unwrap();
+        fs::write(root.path().join("full").join("data.txt"), b"x").unwrap();
+        fs::create_dir(root.path().join("zero")).unwrap();
+        fs::write(root.path().join("zero").join("blank.txt"), b"").unwrap();
+
+        let op = Operation::detached("test");
+        let found = find_empty_items_sync(root.path(), &[], &op).unwrap();
+        let dirs: Vec<(String, u64)> = found
+            .empty_dirs
+            .iter()
+            .map(|dir| (dir.path.clone(), dir.dir_count))
+            .collect();
+        let text = |path: PathBuf| path.to_string_lossy().to_string();
+        assert_eq!(dirs, [(text(root.path().join("a")), 4)]);
+        assert_eq!(
+            found.zero_byte_files,
+            [text(root.path().join("zero").join("blank.txt"))]
+        );
+    }
+
+    #[test]
+    fn roots_taking_in_volumes_or_protected_folders_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let profile = root.path().join("profile");
+        fs::create_dir(&profile).unwrap();
+        let guarded = [profile.clone()];
+        let op = Operation::detached("test");
+
+        assert!(find_empty_items_sync(root.path(), &guarded, &op).is_err());
+        assert!(find_empty_items_sync(&profile, &guarded, &op).is_err());
+        let volume = root.path().ancestors().last().unwrap();
+        assert!(find_empty_items_sync(volume, &[], &op).is_err());
+
+        let target = profile.to_string_lossy().to_string();
+        let result = clean_empty_items_sync(root.path(), vec![target], &guarded, &op);
+        assert_eq!((result.deleted_count, result.failed.len()), (0, 1));
+        assert!(profile.exists());
+    }
+
+    #[test]
+    fn a_refilled_folder_is_reported_once_and_its_empty_siblings_still_go() {
+        let root = tempfile::tempdir().unwrap();
+        let top = root.path().join("top");
+        let deep = top.join("a").join("b");
+        fs::create_dir_all(&deep).unwrap();
+        fs::create_dir_all(top.join("sibling")).unwrap();
+        fs::write(deep.join("late.txt"), b"x").unwrap();
+
+        let op = Operation::detached("test");
+        let target = top.to_string_lossy().to_string();
+        let result = clean_empty_items_sync(root.path(), vec![target], &[], &op);
+        let failed: Vec<&str> = result
+            .failed
+            .iter()
+            .map(|error| error.path.as_str())
+            .collect();
+        assert_eq!(failed, [deep.join("late.txt").to_string_lossy()]);
+        assert_eq!(result.deleted_count, 1);
+        assert!(deep.exists() && !top.join("sibling").exists());
+    }
+}
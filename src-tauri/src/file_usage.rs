@@ -0,0 +1,394 @@
+#[cfg(target_os = "windows")]
+use crate::to_wide_null;
+use crate::{to_unix_ms, ItemDetails};
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use std::{collections::HashMap, fs::File, path::Component, sync::Mutex};
+use std::{fs, path::Path};
+
+const DEFAULT_CLUSTER_BYTES: u64 = 4096;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileId {
+    volume: u64,
+    index: u64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileIdentity {
+    pub(crate) id: FileId,
+    pub(crate) links: u32,
+}
+
+/// `linked` is only set for files with more than one link.
+pub(crate) struct FileUsage {
+    pub(crate) size_bytes: u64,
+    pub(crate) allocated_bytes: u64,
+    pub(crate) linked: Option<FileIdentity>,
+}
+
+impl FileUsage {
+    #[cfg(target_os = "windows")]
+    pub(crate) fn of(path: &Path, metadata: &fs::Metadata, cluster_bytes: u64) -> Self {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Storage::FileSystem::{
+            FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_SPARSE_FILE,
+        };
+
+        let size_bytes = metadata.len();
+        let file = open_for_query(path).ok();
+        let packed = FILE_ATTRIBUTE_COMPRESSED | FILE_ATTRIBUTE_SPARSE_FILE;
+        let allocated_bytes = match &file {
+            Some(file) if metadata.file_attributes() & packed != 0 => {
+                allocation_size(file).unwrap_or(size_bytes)
+            }
+            _ => size_bytes.next_multiple_of(cluster_bytes),
+        };
+        FileUsage {
+            size_bytes,
+            allocated_bytes,
+            linked: file.as_ref().and_then(link_identity),
+        }
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn of(_path: &Path, metadata: &fs::Metadata, _cluster_bytes: u64) -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        let linked = (metadata.nlink() > 1).then(|| FileIdentity {
+            id: FileId {
+                volume: metadata.dev(),
+                index: metadata.ino(),
+            },
+            links: metadata.nlink().min(u32::MAX as u64) as u32,
+        });
+        FileUsage {
+            size_bytes: metadata.len(),
+            allocated_bytes: metadata.blocks().saturating_mul(512),
+            linked,
+        }
+    }
+
+    #[cfg(not(any(unix, target_os = "windows")))]
+    pub(crate) fn of(_path: &Path, metadata: &fs::Metadata, _cluster_bytes: u64) -> Self {
+        FileUsage {
+            size_bytes: metadata.len(),
+            allocated_bytes: metadata.len(),
+            linked: None,
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn links(path: &Path, _metadata: &fs::Metadata) -> Option<FileIdentity> {
+        link_identity(&open_for_query(path).ok()?)
+    }
+
+    #[cfg(unix)]
+    fn links(path: &Path, metadata: &fs::Metadata) -> Option<FileIdentity> {
+        Self::of(path, metadata, DEFAULT_CLUSTER_BYTES).linked
+    }
+
+    #[cfg(not(any(unix, target_os = "windows")))]
+    fn links(_path: &Path, _metadata: &fs::Metadata) -> Option<FileIdentity> {
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn open_for_query(path: &Path) -> std::io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+    };
+
+    fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(path)
+}
+
+#[cfg(target_os = "windows")]
+fn allocation_size(file: &File) -> Option<u64> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileStandardInfo, GetFileInformationByHandleEx, FILE_STANDARD_INFO,
+    };
+
+    let mut standard: FILE_STANDARD_INFO = unsafe { std::mem::zeroed() };
+    let ok = unsafe {
+        GetFileInformationByHandleEx(
+            file.as_raw_handle(),
+            FileStandardInfo,
+            &mut standard as *mut FILE_STANDARD_INFO as *mut _,
+            std::mem::size_of::<FILE_STANDARD_INFO>() as u32,
+        )
+    };
+    (ok != 0).then(|| standard.AllocationSize.max(0) as u64)
+}
+
+#[cfg(target_os = "windows")]
+fn link_identity(file: &File) -> Option<FileIdentity> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) } == 0 {
+        return None;
+    }
+    (info.nNumberOfLinks > 1).then(|| FileIdentity {
+        id: FileId {
+            volume: info.dwVolumeSerialNumber as u64,
+            index: ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+        },
+        links: info.nNumberOfLinks,
+    })
+}
+
+/// Looked up once per walk root rather than per file.
+#[cfg(target_os = "windows")]
+pub(crate) fn cluster_size(path: &Path) -> u64 {
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceW;
+
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return DEFAULT_CLUSTER_BYTES;
+    };
+    let volume = format!("{}\\", prefix.as_os_str().to_string_lossy());
+    let wide = to_wide_null(Path::new(&volume));
+    let (mut sectors, mut sector_bytes, mut free, mut total) = (0, 0, 0, 0);
+    let ok = unsafe {
+        GetDiskFreeSpaceW(
+            wide.as_ptr(),
+            &mut sectors,
+            &mut sector_bytes,
+            &mut free,
+            &mut total,
+        )
+    };
+    match sectors as u64 * sector_bytes as u64 {
+        size if ok != 0 && size > 0 => size,
+        _ => DEFAULT_CLUSTER_BYTES,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn cluster_size(_path: &Path) -> u64 {
+    DEFAULT_CLUSTER_BYTES
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::windows::{fs::OpenOptionsExt, io::AsRawHandle};
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+    };
+
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .ok()?;
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) } == 0 {
+        return None;
+    }
+    Some(FileId {
+        volume: info.dwVolumeSerialNumber as u64,
+        index: ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+    })
+}
+
+#[cfg(unix)]
+pub(crate) fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path).ok()?;
+    Some(FileId {
+        volume: metadata.dev(),
+        index: metadata.ino(),
+    })
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+pub(crate) fn file_id(_path: &Path) -> Option<FileId> {
+    None
+}
+
+/// Nothing while other hard links keep the data alive.
+pub(crate) fn reclaimed_size(path: &Path, metadata: &fs::Metadata) -> u64 {
+    if FileUsage::links(path, metadata).is_some() {
+        0
+    } else {
+        metadata.len()
+    }
+}
+
+impl ItemDetails {
+    pub(crate) fn read(path: &Path, metadata: &fs::Metadata) -> Self {
+        let allocated_bytes = if metadata.is_file() {
+            FileUsage::of(path, metadata, cluster_size(path)).allocated_bytes
+        } else {
+            0
+        };
+        ItemDetails {
+            allocated_bytes,
+            created_ms: metadata.created().ok().and_then(to_unix_ms),
+            accessed_ms: metadata.accessed().ok().and_then(to_unix_ms),
+            owner: file_owner(path, metadata),
+            read_only: metadata.permissions().readonly(),
+            hidden: is_hidden(path, metadata),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn file_owner(path: &Path, _metadata: &fs::Metadata) -> Option<String> {
+    use std::sync::OnceLock;
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{
+        GetLengthSid, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+    };
+    static NAMES: OnceLock<Mutex<HashMap<Vec<u8>, Option<String>>>> = OnceLock::new();
+
+    let wide = to_wide_null(path);
+    let mut owner: PSID = std::ptr::null_mut();
+    let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+    let status = unsafe {
+        GetNamedSecurityInfoW(
+            wide.as_ptr(),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            &mut owner,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut descriptor,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+
+    let names = NAMES.get_or_init(Default::default);
+    let sid =
+        unsafe { std::slice::from_raw_parts(owner as *const u8, GetLengthSid(owner) as usize) }
+            .to_vec();
+    let cached = names
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(&sid)
+        .cloned();
+    let name = cached.unwrap_or_else(|| {
+        let name = account_name(owner);
+        names
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(sid, name.clone());
+        name
+    });
+    unsafe { LocalFree(descriptor) };
+    name
+}
+
+#[cfg(target_os = "windows")]
+fn account_name(sid: windows_sys::Win32::Security::PSID) -> Option<String> {
+    use windows_sys::Win32::Security::LookupAccountSidW;
+
+    let mut name = [0u16; 256];
+    let mut domain = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain_len = domain.len() as u32;
+    let mut sid_use = 0;
+    let ok = unsafe {
+        LookupAccountSidW(
+            std::ptr::null(),
+            sid,
+            name.as_mut_ptr(),
+            &mut name_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+    if domain.is_empty() {
+        Some(name)
+    } else {
+        Some(format!("{}\\{}", domain, name))
+    }
+}
+
+#[cfg(unix)]
+fn file_owner(_path: &Path, metadata: &fs::Metadata) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.uid().to_string())
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn file_owner(_path: &Path, _metadata: &fs::Metadata) -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn is_hidden(_path: &Path, metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN;
+
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_hidden(path: &Path, _metadata: &fs::Metadata) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn details_round_tiny_files_up_and_report_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny.txt");
+        fs::write(&path, [0; 10]).unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        let details = ItemDetails::read(&path, &fs::symlink_metadata(&path).unwrap());
+        assert!(details.allocated_bytes >= 512 && details.allocated_bytes.is_multiple_of(512));
+        assert!(details.read_only);
+        assert!(details.accessed_ms.is_some());
+        assert!(details.owner.is_some());
+
+        let folder = ItemDetails::read(dir.path(), &fs::symlink_metadata(dir.path()).unwrap());
+        assert_eq!(folder.allocated_bytes, 0);
+        assert!(!folder.read_only);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sparse_files_allocate_less_than_their_length() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sparse.img");
+        fs::File::create(&path)
+            .and_then(|file| file.set_len(64 * 1024 * 1024))
+            .unwrap();
+
+        let metadata = fs::symlink_metadata(&path).unwrap();
+        let usage = FileUsage::of(&path, &metadata, cluster_size(&path));
+        assert_eq!(usage.size_bytes, 64 * 1024 * 1024);
+        assert!(usage.allocated_bytes < usage.size_bytes);
+    }
+}
@@ -0,0 +1,428 @@
+use crate::{cutoff::days_ago, normalize_path, normalize_path_str, scan::extension_key};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tauri::{AppHandle, Manager};
+
+const HEURISTICS_FILE: &str = "heuristics.json";
+const ENGLISH_KEYWORDS: [&str; 10] = [
+    "log",
+    "logs",
+    "cache",
+    "caches",
+    "cached",
+    "temp",
+    "temps",
+    "tmp",
+    "temporary",
+    "logfiles",
+];
+
+const CHINESE_KEYWORDS: [&str; 4] = ["缓存", "日志", "临时", "暂存"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HeuristicsConfig {
+    threshold: u32,
+    rules: Vec<HeuristicRule>,
+}
+
+/// All set conditions must hold; within one, any listed value is enough.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HeuristicRule {
+    reason: String,
+    score: u32,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    keyword_sets: Vec<String>,
+    #[serde(skip)]
+    matcher: KeywordMatcher,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    path_patterns: Vec<String>,
+    older_than_days: Option<u64>,
+    larger_than_mb: Option<u64>,
+}
+
+pub(crate) struct Heuristics {
+    pub(crate) threshold: u32,
+    rules: Vec<HeuristicRule>,
+    pub(crate) dir_matcher: KeywordMatcher,
+}
+
+impl Default for HeuristicsConfig {
+    fn default() -> Self {
+        let rule = |reason: &str, score: u32| HeuristicRule {
+            reason: reason.to_string(),
+            score,
+            keywords: Vec::new(),
+            keyword_sets: Vec::new(),
+            matcher: KeywordMatcher::default(),
+            extensions: Vec::new(),
+            path_patterns: Vec::new(),
+            older_than_days: None,
+            larger_than_mb: None,
+        };
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+        Self {
+            threshold: 40,
+            rules: vec![
+                HeuristicRule {
+                    keyword_sets: strings(&["en", "zh"]),
+                    ..rule("名称或路径包含日志、缓存或临时关键字", 40)
+                },
+                HeuristicRule {
+                    extensions: strings(&[".log", ".tmp", ".dmp", ".bak", ".old"]),
+                    ..rule("日志、转储或备份类文件", 30)
+                },
+                HeuristicRule {
+                    path_patterns: strings(&[
+                        "*\\appdata\\local\\temp\\*",
+                        "*\\windows\\temp\\*",
+                        "*\\crashdumps\\*",
+                        "*\\windows\\softwaredistribution\\download\\*",
+                    ]),
+                    ..rule("位于已知的临时或缓存目录", 30)
+                },
+                HeuristicRule {
+                    older_than_days: Some(180),
+                    ..rule("超过 180 天未修改", 20)
+                },
+                HeuristicRule {
+                    larger_than_mb: Some(4096),
+                    ..rule("体积超过 4 GB", 10)
+                },
+            ],
+        }
+    }
+}
+
+impl Heuristics {
+    pub(crate) fn load(app: &AppHandle) -> Result<Self, String> {
+        let path = app
+            .path()
+            .app_config_dir()
+            .map(|dir| dir.join(HEURISTICS_FILE))
+            .map_err(|err| err.to_string())?;
+        let config = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|err| format!("Invalid {}: {}", path.display(), err))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => HeuristicsConfig::default(),
+            Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+        };
+        Self::new(config).map_err(|err| format!("Invalid {}: {}", path.display(), err))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn builtin() -> Self {
+        Self::new(HeuristicsConfig::default()).unwrap()
+    }
+
+    fn new(config: HeuristicsConfig) -> Result<Self, String> {
+        let mut rules = config.rules;
+        let mut all_keywords = Vec::new();
+        for (index, rule) in rules.iter_mut().enumerate() {
+            if rule.keywords.is_empty()
+                && rule.keyword_sets.is_empty()
+                && rule.extensions.is_empty()
+                && rule.path_patterns.is_empty()
+                && rule.older_than_days.is_none()
+                && rule.larger_than_mb.is_none()
+            {
+                return Err(format!("rule {} has no conditions", index + 1));
+            }
+            for set in &rule.keyword_sets {
+                let keywords: &[&str] = match set.as_str() {
+                    "en" => &ENGLISH_KEYWORDS,
+                    "zh" => &CHINESE_KEYWORDS,
+                    _ => return Err(format!("unknown keyword set {} in rule {}", set, index + 1)),
+                };
+                rule.keywords
+                    .extend(keywords.iter().map(|keyword| keyword.to_string()));
+            }
+            rule.matcher = KeywordMatcher::new(&rule.keywords);
+            all_keywords.extend(rule.keywords.iter().cloned());
+            for extension in &mut rule.extensions {
+                *extension = format!(".{}", extension.trim_start_matches('.').to_lowercase());
+            }
+            for pattern in &mut rule.path_patterns {
+                *pattern = normalize_path_str(pattern);
+            }
+        }
+        Ok(Self {
+            threshold: config.threshold,
+            rules,
+            dir_matcher: KeywordMatcher::new(&all_keywords),
+        })
+    }
+
+    pub(crate) fn score(
+        &self,
+        path: &Path,
+        size: u64,
+        modified: Option<SystemTime>,
+        is_dir: bool,
+    ) -> (u32, Vec<String>) {
+        let normalized = normalize_path(path);
+        let extension = extension_key(path);
+        let mut score: u32 = 0;
+        let mut reasons = Vec::new();
+        for rule in &self.rules {
+            let matched = (rule.matcher.is_empty() || rule.matcher.matches_path(path))
+                && (rule.extensions.is_empty()
+                    || (!is_dir && rule.extensions.contains(&extension)))
+                && (rule.path_patterns.is_empty()
+                    || rule
+                        .path_patterns
+                        .iter()
+                        .any(|pattern| matches_pattern(pattern, &normalized)))
+                && rule
+                    .older_than_days
+                    .is_none_or(|days| modified.is_some_and(|modified| modified < days_ago(days)))
+                && rule
+                    .larger_than_mb
+                    .is_none_or(|mb| size > mb.saturating_mul(1024 * 1024));
+            if matched {
+                score = score.saturating_add(rule.score);
+                reasons.push(rule.reason.clone());
+            }
+        }
+        (score, reasons)
+    }
+}
+
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Latin keywords match whole tokens; Chinese ones match as substrings.
+#[derive(Default)]
+pub(crate) struct KeywordMatcher {
+    phrases: Vec<Vec<String>>,
+    chinese: Vec<String>,
+}
+
+impl KeywordMatcher {
+    fn new(keywords: &[String]) -> Self {
+        let mut matcher = Self::default();
+        for keyword in keywords {
+            if keyword.chars().any(is_cjk) {
+                matcher.chinese.push(keyword.trim().to_string());
+                continue;
+            }
+            let phrase = name_tokens(keyword);
+            if !phrase.is_empty() && !matcher.phrases.contains(&phrase) {
+                matcher.phrases.push(phrase);
+            }
+        }
+        matcher
+    }
+
+    fn is_empty(&self) -> bool {
+        self.phrases.is_empty() && self.chinese.is_empty()
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        if self
+            .chinese
+            .iter()
+            .any(|keyword| name.contains(keyword.as_str()))
+        {
+            return true;
+        }
+        if self.phrases.is_empty() {
+            return false;
+        }
+        let tokens = name_tokens(name);
+        self.phrases.iter().any(|phrase| {
+            tokens
+                .windows(phrase.len())
+                .any(|window| window == phrase.as_slice())
+        })
+    }
+
+    fn matches_path(&self, path: &Path) -> bool {
+        path.components()
+            .any(|component| self.matches(&component.as_os_str().to_string_lossy()))
+    }
+}
+
+fn name_tokens(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for (index, &ch) in chars.iter().enumerate() {
+        if !ch.is_alphanumeric() || is_cjk(ch) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if !current.is_empty() {
+            let prev = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            let boundary = (prev.is_lowercase() && ch.is_uppercase())
+                || (prev.is_uppercase() && ch.is_uppercase() && next_is_lower)
+                || prev.is_alphabetic() != ch.is_alphabetic();
+            if boundary {
+                tokens.push(std::mem::take(&mut current));
+            }
+        }
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+pub(crate) fn find_suspicious_dir(
+    path: Option<&Path>,
+    matcher: &KeywordMatcher,
+) -> Option<PathBuf> {
+    let mut current = path?;
+    loop {
+        if let Some(name) = current.file_name() {
+            if matcher.matches(&name.to_string_lossy()) {
+                return Some(current.to_path_buf());
+            }
+        }
+        current = current.parent()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_matcher() -> KeywordMatcher {
+        let keywords: Vec<String> = ENGLISH_KEYWORDS
+            .iter()
+            .chain(CHINESE_KEYWORDS.iter())
+            .map(|keyword| keyword.to_string())
+            .collect();
+        KeywordMatcher::new(&keywords)
+    }
+
+    #[test]
+    fn name_tokens_split_camel_case_and_digits() {
+        assert_eq!(name_tokens("INetCache"), ["i", "net", "cache"]);
+        assert_eq!(name_tokens("GPUCache"), ["gpu", "cache"]);
+        assert_eq!(name_tokens("cache2"), ["cache", "2"]);
+        assert_eq!(name_tokens("app_logs.old"), ["app", "logs", "old"]);
+        assert_eq!(name_tokens("缓存Data"), ["data"]);
+    }
+
+    #[test]
+    fn keywords_inside_other_words_do_not_match() {
+        let matcher = default_matcher();
+        for name in ["catalog", "blog", "dialogs", "Attempt", "Templates"] {
+            assert!(!matcher.matches(name), "{} matched", name);
+        }
+    }
+
+    #[test]
+    fn whole_word_and_chinese_keywords_match() {
+        let matcher = default_matcher();
+        for name in [
+            "logs",
+            "INetCache",
+            "cache2",
+            "app-tmp",
+            "缓存",
+            "日志",
+            "临时",
+            "微信临时文件",
+        ] {
+            assert!(matcher.matches(name), "{} did not match", name);
+        }
+        assert!(!matcher.matches("文档"));
+    }
+
+    #[test]
+    fn multi_word_keywords_match_consecutive_tokens() {
+        let matcher = KeywordMatcher::new(&["crash dumps".to_string()]);
+        assert!(matcher.matches("CrashDumps"));
+        assert!(matcher.matches("crash-dumps-2024"));
+        assert!(!matcher.matches("crash_old_dumps"));
+    }
+
+    #[test]
+    fn path_patterns_match_wildcards_in_order() {
+        let pattern = "*\\appdata\\local\\temp\\*";
+        assert!(matches_pattern(
+            pattern,
+            "c:\\users\\me\\appdata\\local\\temp\\a.tmp"
+        ));
+        assert!(!matches_pattern(
+            pattern,
+            "c:\\users\\me\\appdata\\local\\tempo\\a"
+        ));
+        assert!(matches_pattern("c:\\*.log", "c:\\a\\b.log"));
+        assert!(!matches_pattern("c:\\*.log", "d:\\a\\b.log"));
+        assert!(!matches_pattern("*a*b*", "ba"));
+        assert!(matches_pattern("exact", "exact"));
+        assert!(!matches_pattern("exact", "exactly"));
+    }
+
+    #[test]
+    fn scores_add_up_and_name_their_reasons() {
+        let heuristics = Heuristics::builtin();
+        let old = SystemTime::now() - std::time::Duration::from_secs(200 * 86_400);
+        let path = Path::new("C:\\Users\\me\\AppData\\Local\\Temp\\setup.log");
+        let (score, reasons) = heuristics.score(path, 10, Some(old), false);
+        assert_eq!(score, 40 + 30 + 30 + 20);
+        assert_eq!(reasons.len(), 4);
+
+        let (score, reasons) =
+            heuristics.score(Path::new("D:\\Videos\\movie.mkv"), 10, None, false);
+        assert_eq!((score, reasons.len()), (0, 0));
+        let (score, _) = heuristics.score(Path::new("D:\\Games\\setup.bak"), 10, None, true);
+        assert_eq!(score, 0);
+    }
+
+    #[test]
+    fn configs_with_empty_rules_or_unknown_sets_are_rejected() {
+        let config = |rules: &str| -> HeuristicsConfig {
+            serde_json::from_str(&format!(r#"{{"threshold":10,"rules":[{}]}}"#, rules)).unwrap()
+        };
+        let empty = config(r#"{"reason":"nothing","score":5}"#);
+        assert_eq!(
+            Heuristics::new(empty).err().as_deref(),
+            Some("rule 1 has no conditions")
+        );
+        let unknown = config(r#"{"reason":"r","score":5,"keywordSets":["fr"]}"#);
+        assert!(Heuristics::new(unknown).is_err());
+
+        let custom = config(r#"{"reason":"iso","score":15,"extensions":["ISO"]}"#);
+        let heuristics = Heuristics::new(custom).unwrap();
+        let (score, reasons) = heuristics.score(Path::new("D:\\a.iso"), 1, None, false);
+        assert_eq!((score, reasons), (15, vec!["iso".to_string()]));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{list_disks_sync, path_eq_ignore_case, resolve_volume, to_unix_ms, write_json, DAY_MS};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, SystemTime},
+};
+use tauri::{AppHandle, Manager};
+
+const DISK_HISTORY_FILE: &str = "disk-history.json";
+const DISK_SAMPLE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DISK_SAMPLE_MIN_GAP_MS: i64 = 10 * 60 * 1000;
+pub(crate) const DISK_HISTORY_DAYS: u32 = 180;
+pub(crate) const DEFAULT_HISTORY_DAYS: u32 = 30;
+const FORECAST_WINDOW_DAYS: i64 = 14;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskSample {
+    time_ms: i64,
+    total_bytes: u64,
+    used_bytes: u64,
+    free_bytes: u64,
+}
+
+/// `full_at_ms` is only set while usage grows.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiskHistory {
+    mount_point: String,
+    samples: Vec<DiskSample>,
+    growth_bytes_per_day: Option<f64>,
+    full_at_ms: Option<i64>,
+    days_until_full: Option<f64>,
+}
+
+#[derive(Default)]
+pub(crate) struct DiskHistoryLock(Mutex<()>);
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskHistoryFile {
+    volumes: HashMap<String, Vec<DiskSample>>,
+}
+
+impl DiskHistoryFile {
+    fn load(path: &Path) -> Self {
+        File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        write_json(path, self)
+    }
+}
+
+fn disk_history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(DISK_HISTORY_FILE))
+        .map_err(|err| err.to_string())
+}
+
+pub(crate) fn start_disk_sampler(app: AppHandle) {
+    if cfg!(not(target_os = "windows")) {
+        return;
+    }
+    thread::spawn(move || loop {
+        let _ = record_disk_samples(&app);
+        thread::sleep(DISK_SAMPLE_INTERVAL);
+    });
+}
+
+fn record_disk_samples(app: &AppHandle) -> Result<(), String> {
+    let path = disk_history_path(app)?;
+    let lock = app.state::<DiskHistoryLock>();
+    let _guard = lock.0.lock().unwrap_or_else(|err| err.into_inner());
+    let mut history = DiskHistoryFile::load(&path);
+    let now_ms = to_unix_ms(SystemTime::now()).unwrap_or(0);
+    let oldest_ms = now_ms - i64::from(DISK_HISTORY_DAYS) * DAY_MS;
+    for info in list_disks_sync()? {
+        let samples = history.volumes.entry(info.mount_point).or_default();
+        samples.retain(|sample| sample.time_ms >= oldest_ms);
+        if samples
+            .last()
+            .is_some_and(|last| now_ms - last.time_ms < DISK_SAMPLE_MIN_GAP_MS)
+        {
+            continue;
+        }
+        samples.push(DiskSample {
+            time_ms: now_ms,
+            total_bytes: info.total_bytes,
+            used_bytes: info.used_bytes,
+            free_bytes: info.free_bytes,
+        });
+    }
+    history.save(&path)
+}
+
+pub(crate) fn get_disk_history_sync(
+    app: &AppHandle,
+    mount_point: Option<&str>,
+    days: u32,
+) -> Result<DiskHistory, String> {
+    let volume = resolve_volume(mount_point)?;
+    record_disk_samples(app)?;
+    let history = {
+        let lock = app.state::<DiskHistoryLock>();
+        let _guard = lock.0.lock().unwrap_or_else(|err| err.into_inner());
+        DiskHistoryFile::load(&disk_history_path(app)?)
+    };
+    let samples = history
+        .volumes
+        .into_iter()
+        .find(|(mount_point, _)| path_eq_ignore_case(Path::new(mount_point), &volume))
+        .map(|(_, samples)| samples)
+        .unwrap_or_default();
+
+    let now_ms = to_unix_ms(SystemTime::now()).unwrap_or(0);
+    let recent: Vec<&DiskSample> = samples
+        .iter()
+        .filter(|sample| now_ms - sample.time_ms <= FORECAST_WINDOW_DAYS * DAY_MS)
+        .collect();
+    let growth_bytes_per_day = usage_trend(&recent);
+    let days_until_full = growth_bytes_per_day
+        .filter(|growth| *growth > 0.0)
+        .zip(samples.last())
+        .map(|(growth, last)| last.free_bytes as f64 / growth);
+    let full_at_ms = days_until_full
+        .zip(samples.last())
+        .map(|(days, last)| last.time_ms + (days * DAY_MS as f64) as i64);
+
+    let since_ms = now_ms - i64::from(days) * DAY_MS;
+    Ok(DiskHistory {
+        mount_point: volume.to_string_lossy().to_string(),
+        samples: samples
+            .into_iter()
+            .filter(|sample| sample.time_ms >= since_ms)
+            .collect(),
+        growth_bytes_per_day,
+        full_at_ms,
+        days_until_full,
+    })
+}
+
+/// Least-squares bytes per day; needs samples spread over at least a day.
+fn usage_trend(samples: &[&DiskSample]) -> Option<f64> {
+    let first = samples.first()?;
+    let last = samples.last()?;
+    if last.time_ms - first.time_ms < DAY_MS {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|sample| {
+            (
+                (sample.time_ms - first.time_ms) as f64 / DAY_MS as f64,
+                sample.used_bytes as f64,
+            )
+        })
+        .collect();
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let (covariance, variance) = points.iter().fold((0.0, 0.0), |(cov, var), (x, y)| {
+        (
+            cov + (x - mean_x) * (y - mean_y),
+            var + (x - mean_x) * (x - mean_x),
+        )
+    });
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time_ms: i64, used_bytes: u64) -> DiskSample {
+        DiskSample {
+            time_ms,
+            total_bytes: 1 << 40,
+            used_bytes,
+            free_bytes: (1 << 40) - used_bytes,
+        }
+    }
+
+    #[test]
+    fn trend_is_the_least_squares_growth_per_day() {
+        let hourly: Vec<DiskSample> = (0..72)
+            .map(|hour| sample(hour * DAY_MS / 24, 500_000_000 + hour as u64 * 10_000_000))
+            .collect();
+        let samples: Vec<&DiskSample> = hourly.iter().collect();
+        let growth = usage_trend(&samples).unwrap();
+        assert!((growth - 240_000_000.0).abs() < 1.0, "{}", growth);
+
+        let flat = [sample(0, 100), sample(DAY_MS, 100), sample(2 * DAY_MS, 100)];
+        let samples: Vec<&DiskSample> = flat.iter().collect();
+        assert_eq!(usage_trend(&samples), Some(0.0));
+    }
+
+    #[test]
+    fn trend_needs_samples_spread_over_a_day() {
+        let short = [sample(0, 100), sample(DAY_MS - 1, 900)];
+        let samples: Vec<&DiskSample> = short.iter().collect();
+        assert_eq!(usage_trend(&samples), None);
+        assert_eq!(usage_trend(&[]), None);
+    }
+}
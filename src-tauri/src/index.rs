@@ -0,0 +1,486 @@
+use crate::{
+    file_usage::{FileIdentity, FileUsage},
+    normalize_path,
+    operation::Operation,
+    scan::{extension_key, modified_hour, FileBreakdown},
+    to_unix_ms, write_json, DAY_MS,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+    time::SystemTime,
+};
+use tauri::{AppHandle, Manager};
+
+const SCAN_INDEX_DIR: &str = "scan-index";
+const SCAN_INDEX_VERSION: u32 = 5;
+pub(crate) const INDEX_LARGE_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// `file_bytes` only covers files with a single link.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DirRecord {
+    modified_ms: Option<i64>,
+    pub(crate) file_bytes: u64,
+    pub(crate) allocated_bytes: u64,
+    pub(crate) file_count: u64,
+    pub(crate) files: FileBreakdown,
+    pub(crate) subdirs: Vec<String>,
+    pub(crate) linked_files: Vec<IndexedLink>,
+    pub(crate) large_files: Vec<IndexedFile>,
+    recent_files: Vec<IndexedFile>,
+}
+
+impl DirRecord {
+    pub(crate) fn new(modified_ms: Option<i64>) -> Self {
+        Self {
+            modified_ms,
+            file_bytes: 0,
+            allocated_bytes: 0,
+            file_count: 0,
+            files: FileBreakdown::default(),
+            subdirs: Vec::new(),
+            linked_files: Vec::new(),
+            large_files: Vec::new(),
+            recent_files: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add_file(
+        &mut self,
+        name: String,
+        metadata: &fs::Metadata,
+        usage: &FileUsage,
+        recent_after_ms: i64,
+    ) {
+        let size = usage.size_bytes;
+        let extension = extension_key(Path::new(&name));
+        let hour = modified_hour(metadata);
+        self.file_count += 1;
+        match usage.linked {
+            Some(identity) => self.linked_files.push(IndexedLink {
+                identity,
+                size_bytes: size,
+                allocated_bytes: usage.allocated_bytes,
+                extension,
+                hour,
+            }),
+            None => {
+                self.file_bytes = self.file_bytes.saturating_add(size);
+                self.allocated_bytes = self.allocated_bytes.saturating_add(usage.allocated_bytes);
+                self.files.add(&extension, hour, size, 1);
+            }
+        }
+        let modified_ms = metadata.modified().ok().and_then(to_unix_ms);
+        let file = IndexedFile {
+            name,
+            size_bytes: size,
+            modified_ms,
+            linked: usage.linked,
+        };
+        if size >= INDEX_LARGE_FILE_BYTES {
+            self.large_files.push(file);
+        } else if modified_ms.is_none_or(|modified| modified >= recent_after_ms) {
+            self.recent_files.push(file);
+        }
+    }
+
+    /// Growing a file in place leaves the directory's mtime alone, so the
+    /// files most likely to do that are checked one by one.
+    fn is_current(&self, dir: &Path) -> bool {
+        self.large_files
+            .iter()
+            .chain(&self.recent_files)
+            .all(|file| {
+                fs::symlink_metadata(dir.join(&file.name)).is_ok_and(|metadata| {
+                    metadata.len() == file.size_bytes
+                        && metadata.modified().ok().and_then(to_unix_ms) == file.modified_ms
+                })
+            })
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexedFile {
+    pub(crate) name: String,
+    pub(crate) size_bytes: u64,
+    pub(crate) modified_ms: Option<i64>,
+    pub(crate) linked: Option<FileIdentity>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexedLink {
+    pub(crate) identity: FileIdentity,
+    pub(crate) size_bytes: u64,
+    pub(crate) allocated_bytes: u64,
+    pub(crate) extension: String,
+    pub(crate) hour: i64,
+}
+
+/// The records saved for one walk root, in a file of their own.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RootIndex {
+    version: u32,
+    root: String,
+    dirs: HashMap<String, DirRecord>,
+}
+
+struct IndexStore {
+    dir: PathBuf,
+    roots: Vec<IndexRoot>,
+}
+
+struct IndexRoot {
+    key: String,
+    prefix: String,
+    file: PathBuf,
+}
+
+impl IndexStore {
+    fn new(dir: &Path, roots: &[PathBuf]) -> Self {
+        let mut keys: Vec<String> = roots.iter().map(|root| normalize_path(root)).collect();
+        keys.sort();
+        keys.dedup();
+        let roots = keys
+            .into_iter()
+            .map(|key| {
+                let digest = Sha256::digest(key.as_bytes());
+                let name: String = digest[..8]
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect();
+                IndexRoot {
+                    prefix: format!("{}\\", key.trim_end_matches('\\')),
+                    file: dir.join(format!("{}.json", name)),
+                    key,
+                }
+            })
+            .collect();
+        Self {
+            dir: dir.to_path_buf(),
+            roots,
+        }
+    }
+
+    fn load(&self) -> HashMap<String, DirRecord> {
+        let mut dirs = HashMap::new();
+        for root in &self.roots {
+            let index = File::open(&root.file)
+                .ok()
+                .and_then(|file| serde_json::from_reader::<_, RootIndex>(BufReader::new(file)).ok())
+                .filter(|index| index.version == SCAN_INDEX_VERSION && index.root == root.key);
+            if let Some(index) = index {
+                dirs.extend(index.dirs);
+            }
+        }
+        dirs
+    }
+
+    /// Each record goes to the innermost root that holds it; only roots with
+    /// a new record or a record that is gone are written back.
+    fn save(&self, records: Vec<KeptRecord>, unused: HashMap<String, DirRecord>) {
+        let mut changed = vec![false; self.roots.len()];
+        let mut dirs: Vec<HashMap<String, DirRecord>> =
+            self.roots.iter().map(|_| HashMap::new()).collect();
+        for key in unused.into_keys() {
+            if let Some(position) = self.owner(&key) {
+                changed[position] = true;
+            }
+        }
+        for kept in records {
+            if let Some(position) = self.owner(&kept.key) {
+                changed[position] |= kept.fresh;
+                dirs[position].insert(kept.key, kept.record);
+            }
+        }
+        if changed.iter().any(|changed| *changed) && fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        for ((root, dirs), changed) in self.roots.iter().zip(dirs).zip(changed) {
+            if !changed {
+                continue;
+            }
+            if dirs.is_empty() {
+                let _ = fs::remove_file(&root.file);
+                continue;
+            }
+            let index = RootIndex {
+                version: SCAN_INDEX_VERSION,
+                root: root.key.clone(),
+                dirs,
+            };
+            let _ = write_json(&root.file, &index);
+        }
+    }
+
+    fn owner(&self, key: &str) -> Option<usize> {
+        self.roots
+            .iter()
+            .enumerate()
+            .filter(|(_, root)| key == root.key || key.starts_with(&root.prefix))
+            .max_by_key(|(_, root)| root.key.len())
+            .map(|(position, _)| position)
+    }
+}
+
+struct KeptRecord {
+    key: String,
+    record: DirRecord,
+    fresh: bool,
+}
+
+pub(crate) struct IndexSession {
+    previous: Mutex<HashMap<String, DirRecord>>,
+    records: Mutex<Vec<KeptRecord>>,
+    pub(crate) recent_after_ms: i64,
+}
+
+pub(crate) struct OpenDir {
+    pub(crate) depth: usize,
+    pub(crate) key: String,
+    pub(crate) record: DirRecord,
+    pub(crate) complete: bool,
+}
+
+impl IndexSession {
+    pub(crate) fn detached() -> Self {
+        Self::new(HashMap::new())
+    }
+
+    fn new(previous: HashMap<String, DirRecord>) -> Self {
+        let now_ms = to_unix_ms(SystemTime::now()).unwrap_or(0);
+        IndexSession {
+            previous: Mutex::new(previous),
+            records: Mutex::new(Vec::new()),
+            recent_after_ms: now_ms - DAY_MS,
+        }
+    }
+
+    /// Takes the record of an unchanged directory out of the previous index.
+    pub(crate) fn reuse(
+        &self,
+        dir: &Path,
+        key: &str,
+        modified_ms: Option<i64>,
+    ) -> Option<DirRecord> {
+        let modified_ms = modified_ms?;
+        let record = self
+            .previous
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(key)?;
+        (record.modified_ms == Some(modified_ms) && record.is_current(dir)).then_some(record)
+    }
+
+    pub(crate) fn replayed(&self, key: String, record: DirRecord) {
+        self.keep(key, record, false);
+    }
+
+    pub(crate) fn close_dirs(&self, open: &mut Vec<OpenDir>, depth: usize) {
+        while open.last().is_some_and(|dir| dir.depth >= depth) {
+            if let Some(dir) = open.pop().filter(|dir| dir.complete) {
+                self.keep(dir.key, dir.record, true);
+            }
+        }
+    }
+
+    fn keep(&self, key: String, record: DirRecord, fresh: bool) {
+        self.lock().push(KeptRecord { key, record, fresh });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<KeptRecord>> {
+        self.records.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn into_parts(self) -> (Vec<KeptRecord>, HashMap<String, DirRecord>) {
+        let records = self
+            .records
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner());
+        let unused = self
+            .previous
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner());
+        (records, unused)
+    }
+}
+
+/// A cancelled scan leaves the index untouched.
+pub(crate) fn with_scan_index<T>(
+    op: &Operation,
+    force_rescan: bool,
+    roots: &[PathBuf],
+    scan: impl FnOnce(&IndexSession) -> T,
+) -> T {
+    let dir = op.app().and_then(scan_index_dir);
+    with_index_dir(dir.as_deref(), op, force_rescan, roots, scan)
+}
+
+fn with_index_dir<T>(
+    dir: Option<&Path>,
+    op: &Operation,
+    force_rescan: bool,
+    roots: &[PathBuf],
+    scan: impl FnOnce(&IndexSession) -> T,
+) -> T {
+    let Some(store) = dir.map(|dir| IndexStore::new(dir, roots)) else {
+        return scan(&IndexSession::detached());
+    };
+    let previous = if force_rescan {
+        HashMap::new()
+    } else {
+        store.load()
+    };
+    let session = IndexSession::new(previous);
+    let result = scan(&session);
+    if !op.is_cancelled() {
+        let (records, unused) = session.into_parts();
+        store.save(records, unused);
+    }
+    result
+}
+
+fn scan_index_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_local_data_dir()
+        .ok()
+        .map(|dir| dir.join(SCAN_INDEX_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::{scan_root, CategoryScan};
+    use std::{io::Write, time::Duration};
+
+    fn scan(index_dir: &Path, root: &Path, force_rescan: bool) -> (CategoryScan, usize) {
+        let op = Operation::detached("test");
+        let roots = [root.to_path_buf()];
+        with_index_dir(Some(index_dir), &op, force_rescan, &roots, |index| {
+            let scan = scan_root(root, None, &op, index);
+            let fresh = index.lock().iter().filter(|kept| kept.fresh).count();
+            (scan, fresh)
+        })
+    }
+
+    /// Works on directories too, so a later change always moves their mtime.
+    fn set_old(path: &Path) {
+        let old = SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60);
+        let mut options = File::options();
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            use windows_sys::Win32::Storage::FileSystem::{
+                FILE_FLAG_BACKUP_SEMANTICS, FILE_WRITE_ATTRIBUTES,
+            };
+            options
+                .access_mode(FILE_WRITE_ATTRIBUTES)
+                .custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+        }
+        #[cfg(not(target_os = "windows"))]
+        options.read(true);
+        options
+            .open(path)
+            .and_then(|file| file.set_modified(old))
+            .unwrap();
+    }
+
+    #[test]
+    fn unchanged_dirs_are_replayed_without_rewriting_the_index() {
+        let index_dir = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("a")).unwrap();
+        fs::create_dir(root.path().join("b")).unwrap();
+        fs::write(root.path().join("a").join("x.bin"), [0; 100]).unwrap();
+        fs::write(root.path().join("b").join("y.bin"), [0; 200]).unwrap();
+
+        let (first, fresh) = scan(index_dir.path(), root.path(), false);
+        assert_eq!((first.size_bytes, first.file_count, fresh), (300, 2, 3));
+        let index_file = fs::read_dir(index_dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        set_old(&index_file);
+        let saved = fs::metadata(&index_file).unwrap().modified().unwrap();
+
+        let (second, fresh) = scan(index_dir.path(), root.path(), false);
+        assert_eq!((second.size_bytes, second.file_count, fresh), (300, 2, 0));
+        assert_eq!(
+            fs::metadata(&index_file).unwrap().modified().unwrap(),
+            saved
+        );
+
+        let (forced, fresh) = scan(index_dir.path(), root.path(), true);
+        assert_eq!((forced.size_bytes, fresh), (300, 3));
+    }
+
+    #[test]
+    fn files_grown_in_place_are_not_replayed_at_stale_sizes() {
+        let index_dir = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("logs")).unwrap();
+        fs::create_dir(root.path().join("data")).unwrap();
+        let log = root.path().join("logs").join("app.log");
+        fs::write(&log, [0; 100]).unwrap();
+        let big = root.path().join("data").join("big.bin");
+        File::create(&big)
+            .and_then(|file| file.set_len(INDEX_LARGE_FILE_BYTES))
+            .unwrap();
+        set_old(&big);
+        let (first, _) = scan(index_dir.path(), root.path(), false);
+        assert_eq!(first.size_bytes, INDEX_LARGE_FILE_BYTES + 100);
+
+        File::options()
+            .append(true)
+            .open(&log)
+            .and_then(|mut file| file.write_all(&[0; 50]))
+            .unwrap();
+        File::options()
+            .write(true)
+            .open(&big)
+            .and_then(|file| file.set_len(INDEX_LARGE_FILE_BYTES + 1000))
+            .unwrap();
+        set_old(&big);
+
+        let (second, fresh) = scan(index_dir.path(), root.path(), false);
+        assert_eq!(second.size_bytes, INDEX_LARGE_FILE_BYTES + 1150);
+        assert_eq!(fresh, 2);
+    }
+
+    #[test]
+    fn removed_dirs_drop_out_of_totals_and_the_index() {
+        let index_dir = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("kept")).unwrap();
+        fs::create_dir(root.path().join("removed")).unwrap();
+        fs::write(root.path().join("kept").join("x.bin"), [0; 100]).unwrap();
+        fs::write(root.path().join("removed").join("y.bin"), [0; 200]).unwrap();
+        set_old(root.path());
+        scan(index_dir.path(), root.path(), false);
+
+        fs::remove_dir_all(root.path().join("removed")).unwrap();
+        let (after, fresh) = scan(index_dir.path(), root.path(), false);
+        assert_eq!((after.size_bytes, after.file_count, fresh), (100, 1, 1));
+
+        let store = IndexStore::new(index_dir.path(), &[root.path().to_path_buf()]);
+        let mut keys: Vec<String> = store.load().into_keys().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                normalize_path(root.path()),
+                normalize_path(&root.path().join("kept"))
+            ]
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{
+    cutoff::FileStat,
+    cutoff_time,
+    file_usage::{FileId, FileIdentity, FileUsage},
+    from_unix_ms,
+    heuristics::{find_suspicious_dir, Heuristics},
+    index::{DirRecord, INDEX_LARGE_FILE_BYTES},
+    match_category_id, normalize_path,
+    scan::ScanVisitor,
+    to_unix_ms, CategoryDef, ItemDetails, LargeItem,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub(crate) struct LargeFiles<'a> {
+    categories: &'a [CategoryDef],
+    heuristics: &'a Heuristics,
+    min_size_bytes: u64,
+    pub(crate) top: TopItems,
+    /// Roots of categories whose cutoff reads times the index does not keep.
+    file_time_prefixes: Vec<String>,
+}
+
+impl<'a> LargeFiles<'a> {
+    pub(crate) fn new(
+        categories: &'a [CategoryDef],
+        heuristics: &'a Heuristics,
+        min_size_bytes: u64,
+        limit: usize,
+    ) -> Self {
+        let file_time_prefixes = categories
+            .iter()
+            .filter(|def| cutoff_time(&def.kind).is_some_and(|cutoff| cutoff.uses_file_times()))
+            .flat_map(|def| def.roots.iter())
+            .map(|root| format!("{}\\", normalize_path(root).trim_end_matches('\\')))
+            .collect();
+        Self {
+            categories,
+            heuristics,
+            min_size_bytes,
+            top: TopItems::new(limit),
+            file_time_prefixes,
+        }
+    }
+}
+
+impl LargeFiles<'_> {
+    fn push(&mut self, path: &Path, file: FileStat, linked: Option<FileIdentity>) {
+        let size = file.size;
+        let modified = file.modified;
+        if size < self.min_size_bytes || !self.top.admits(size) {
+            return;
+        }
+        let name = path
+            .file_name()
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path_text = path.to_string_lossy();
+        let (score, reasons) = self.heuristics.score(path, size, modified, false);
+        let category_id = match_category_id(path, &file, self.categories);
+        self.top.push(LargeItem {
+            path: path_text.to_string(),
+            name,
+            size_bytes: size,
+            reclaimable_bytes: if linked.is_some() { 0 } else { size },
+            modified_ms: modified.and_then(to_unix_ms),
+            is_dir: false,
+            suspicious: score >= self.heuristics.threshold,
+            score,
+            reasons,
+            category_id,
+            details: ItemDetails::default(),
+            file_id: linked.map(|identity| identity.id),
+        });
+    }
+}
+
+impl LargeItem {
+    pub(crate) fn read_details(&mut self) {
+        let path = Path::new(&self.path);
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return;
+        };
+        let allocated_bytes = self.details.allocated_bytes;
+        self.details = ItemDetails::read(path, &metadata);
+        if self.is_dir {
+            self.details.allocated_bytes = allocated_bytes;
+        } else {
+            self.modified_ms = metadata.modified().ok().and_then(to_unix_ms);
+        }
+    }
+}
+
+impl ScanVisitor for LargeFiles<'_> {
+    fn visit_file(&mut self, path: &Path, metadata: &fs::Metadata, usage: &FileUsage) {
+        self.push(path, FileStat::of(metadata), usage.linked);
+    }
+
+    fn needs_files(&self, dir: &Path) -> bool {
+        if self.min_size_bytes < INDEX_LARGE_FILE_BYTES {
+            return true;
+        }
+        if self.file_time_prefixes.is_empty() {
+            return false;
+        }
+        let target = format!("{}\\", normalize_path(dir).trim_end_matches('\\'));
+        self.file_time_prefixes
+            .iter()
+            .any(|prefix| target.starts_with(prefix.as_str()))
+    }
+
+    fn visit_record(&mut self, dir: &Path, record: &DirRecord) {
+        for file in &record.large_files {
+            let stat = FileStat {
+                size: file.size_bytes,
+                modified: file.modified_ms.map(from_unix_ms),
+                accessed: None,
+                created: None,
+            };
+            self.push(&dir.join(&file.name), stat, file.linked);
+        }
+    }
+}
+
+pub(crate) struct TopItems {
+    limit: usize,
+    heap: BinaryHeap<Reverse<BySize>>,
+    file_ids: HashSet<FileId>,
+}
+
+struct BySize(LargeItem);
+
+impl PartialEq for BySize {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for BySize {}
+
+impl PartialOrd for BySize {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BySize {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .size_bytes
+            .cmp(&other.0.size_bytes)
+            .then_with(|| other.0.path.cmp(&self.0.path))
+    }
+}
+
+impl TopItems {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::new(),
+            file_ids: HashSet::new(),
+        }
+    }
+
+    fn admits(&self, size: u64) -> bool {
+        self.heap.len() < self.limit
+            || self
+                .heap
+                .peek()
+                .is_some_and(|Reverse(smallest)| size > smallest.0.size_bytes)
+    }
+
+    fn push(&mut self, item: LargeItem) {
+        if !self.admits(item.size_bytes) {
+            return;
+        }
+        if let Some(id) = item.file_id {
+            if !self.file_ids.insert(id) {
+                return;
+            }
+        }
+        self.heap.push(Reverse(BySize(item)));
+        if self.heap.len() > self.limit {
+            if let Some(Reverse(BySize(evicted))) = self.heap.pop() {
+                if let Some(id) = evicted.file_id {
+                    self.file_ids.remove(&id);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: TopItems) {
+        for Reverse(BySize(item)) in other.heap {
+            self.push(item);
+        }
+    }
+
+    pub(crate) fn into_sorted(self) -> Vec<LargeItem> {
+        let mut items: Vec<LargeItem> = self
+            .heap
+            .into_iter()
+            .map(|Reverse(BySize(item))| item)
+            .collect();
+        items.sort_by_key(|item| Reverse(item.size_bytes));
+        items
+    }
+}
+
+/// Only the keyword folders along the current walk path are open at a time.
+pub(crate) struct SuspiciousDirs<'a> {
+    heuristics: &'a Heuristics,
+    min_size_bytes: u64,
+    open: Vec<(PathBuf, DirTotals)>,
+    pub(crate) top: TopItems,
+}
+
+#[derive(Default)]
+struct DirTotals {
+    size_bytes: u64,
+    allocated_bytes: u64,
+    unlinked_bytes: u64,
+    /// Size, link count and links seen.
+    linked: HashMap<FileId, (u64, u32, u32)>,
+}
+
+impl DirTotals {
+    fn add(&mut self, size_bytes: u64, allocated_bytes: u64, linked: Option<FileIdentity>) {
+        let Some(identity) = linked else {
+            self.size_bytes += size_bytes;
+            self.allocated_bytes += allocated_bytes;
+            self.unlinked_bytes += size_bytes;
+            return;
+        };
+        let file = self
+            .linked
+            .entry(identity.id)
+            .or_insert((size_bytes, identity.links, 0));
+        if file.2 == 0 {
+            self.size_bytes += size_bytes;
+            self.allocated_bytes += allocated_bytes;
+        }
+        file.2 += 1;
+    }
+
+    fn reclaimable_bytes(&self) -> u64 {
+        let linked: u64 = self
+            .linked
+            .values()
+            .filter(|(_, links, seen)| seen >= links)
+            .map(|(size, _, _)| size)
+            .sum();
+        self.unlinked_bytes + linked
+    }
+}
+
+impl<'a> SuspiciousDirs<'a> {
+    pub(crate) fn new(heuristics: &'a Heuristics, min_size_bytes: u64, limit: usize) -> Self {
+        Self {
+            heuristics,
+            min_size_bytes,
+            open: Vec::new(),
+            top: TopItems::new(limit),
+        }
+    }
+
+    fn totals_for(&mut self, dir: &Path) -> Option<&mut DirTotals> {
+        let suspicious_dir = find_suspicious_dir(Some(dir), &self.heuristics.dir_matcher)?;
+        while self
+            .open
+            .last()
+            .is_some_and(|(open_dir, _)| !suspicious_dir.starts_with(open_dir))
+        {
+            self.close_last();
+        }
+        if self
+            .open
+            .last()
+            .is_none_or(|(open_dir, _)| *open_dir != suspicious_dir)
+        {
+            self.open.push((suspicious_dir, DirTotals::default()));
+        }
+        self.open.last_mut().map(|(_, totals)| totals)
+    }
+
+    fn close_last(&mut self) {
+        let Some((path, totals)) = self.open.pop() else {
+            return;
+        };
+        if totals.size_bytes < self.min_size_bytes || !self.top.admits(totals.size_bytes) {
+            return;
+        }
+        let name = path
+            .file_name()
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        let (score, reasons) = self.heuristics.score(&path, totals.size_bytes, None, true);
+        self.top.push(LargeItem {
+            path: path.to_string_lossy().to_string(),
+            name,
+            size_bytes: totals.size_bytes,
+            reclaimable_bytes: totals.reclaimable_bytes(),
+            modified_ms: None,
+            is_dir: true,
+            suspicious: score >= self.heuristics.threshold,
+            score,
+            reasons,
+            category_id: None,
+            details: ItemDetails {
+                allocated_bytes: totals.allocated_bytes,
+                ..ItemDetails::default()
+            },
+            file_id: None,
+        });
+    }
+
+    pub(crate) fn finish(&mut self) {
+        while !self.open.is_empty() {
+            self.close_last();
+        }
+    }
+}
+
+impl ScanVisitor for SuspiciousDirs<'_> {
+    fn visit_file(&mut self, path: &Path, _metadata: &fs::Metadata, usage: &FileUsage) {
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        if let Some(totals) = self.totals_for(dir) {
+            totals.add(usage.size_bytes, usage.allocated_bytes, usage.linked);
+        }
+    }
+
+    fn needs_files(&self, _dir: &Path) -> bool {
+        false
+    }
+
+    fn visit_record(&mut self, dir: &Path, record: &DirRecord) {
+        if let Some(totals) = self.totals_for(dir) {
+            totals.add(record.file_bytes, record.allocated_bytes, None);
+            for file in &record.linked_files {
+                totals.add(file.size_bytes, file.allocated_bytes, Some(file.identity));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, size_bytes: u64, file_id: Option<FileId>) -> LargeItem {
+        LargeItem {
+            path: path.to_string(),
+            name: path.to_string(),
+            size_bytes,
+            reclaimable_bytes: size_bytes,
+            modified_ms: None,
+            is_dir: false,
+            suspicious: false,
+            score: 0,
+            reasons: Vec::new(),
+            category_id: None,
+            details: ItemDetails::default(),
+            file_id,
+        }
+    }
+
+    fn paths(top: TopItems) -> Vec<String> {
+        top.into_sorted()
+            .into_iter()
+            .map(|item| item.path)
+            .collect()
+    }
+
+    #[test]
+    fn top_items_keep_only_the_largest_across_merges() {
+        let mut first = TopItems::new(3);
+        let mut second = TopItems::new(3);
+        for (index, size) in [5, 90, 40, 70, 10].into_iter().enumerate() {
+            first.push(item(&format!("a{}", index), size, None));
+        }
+        for (index, size) in [80, 20, 60].into_iter().enumerate() {
+            second.push(item(&format!("b{}", index), size, None));
+        }
+        assert_eq!(first.heap.len(), 3);
+        first.merge(second);
+        assert_eq!(paths(first), ["a1", "b0", "a3"]);
+    }
+
+    #[test]
+    fn hard_links_take_one_slot_until_evicted() {
+        let id: FileId = serde_json::from_str(r#"{"volume":1,"index":42}"#).unwrap();
+        let mut top = TopItems::new(2);
+        top.push(item("link-a", 50, Some(id)));
+        top.push(item("link-b", 50, Some(id)));
+        top.push(item("other", 40, None));
+        assert_eq!(paths(top), ["link-a", "other"]);
+
+        let mut top = TopItems::new(1);
+        top.push(item("link-a", 50, Some(id)));
+        top.push(item("bigger", 60, None));
+        top.push(item("link-b", 55, Some(id)));
+        assert!(top.file_ids.is_empty());
+        top.push(item("biggest", 70, None));
+        assert_eq!(paths(top), ["biggest"]);
+    }
+
+    #[test]
+    fn suspicious_dirs_close_as_the_walk_leaves_them() {
+        let heuristics = Heuristics::builtin();
+        let mut dirs = SuspiciousDirs::new(&heuristics, 1, 10);
+        let usage = |size_bytes| FileUsage {
+            size_bytes,
+            allocated_bytes: size_bytes,
+            linked: None,
+        };
+        let metadata = fs::metadata(".").unwrap();
+        for (path, size) in [
+            ("/data/app/cache/a.bin", 100),
+            ("/data/app/cache/nested/b.bin", 50),
+            ("/data/app/docs/c.bin", 900),
+            ("/data/logs/d.bin", 30),
+        ] {
+            dirs.visit_file(Path::new(path), &metadata, &usage(size));
+        }
+        assert_eq!(dirs.open.len(), 1);
+        dirs.finish();
+
+        let totals: Vec<(String, u64)> = dirs
+            .top
+            .into_sorted()
+            .into_iter()
+            .map(|item| (item.name, item.size_bytes))
+            .collect();
+        assert_eq!(
+            totals,
+            [("cache".to_string(), 150), ("logs".to_string(), 30)]
+        );
+    }
+}
@@ -1,30 +1,81 @@
+mod browsers;
+mod custom_categories;
+mod cutoff;
+mod duplicates;
+mod empty;
+mod file_usage;
+mod heuristics;
+mod history;
+mod index;
+mod large_items;
+mod listing;
+mod operation;
+mod providers;
+mod scan;
+mod snapshots;
+mod tree;
+
+use browsers::{scan_browser_caches_sync, BrowserCache, BrowserProfile, BrowserProfiles};
+use custom_categories::load_categories;
+use cutoff::{Cutoff, FilePredicate, FileStat};
+use duplicates::{
+    clean_duplicates_sync, default_duplicate_roots, find_duplicates_sync, DuplicateGroup,
+    DuplicateSelection, DEFAULT_DUPLICATE_MIN_MB,
+};
+use empty::{clean_empty_items_sync, find_empty_items_sync, guarded_dirs, EmptyItems};
+use file_usage::{reclaimed_size, FileId};
+use heuristics::Heuristics;
+use history::{
+    get_disk_history_sync, start_disk_sampler, DiskHistory, DiskHistoryLock, DEFAULT_HISTORY_DAYS,
+    DISK_HISTORY_DAYS,
+};
+use listing::{list_category_items_sync, ListQuery, Listings};
+use operation::{Operation, Operations};
+use providers::{remove_tree, CacheProvider, CleanupProvider, FileProvider, RecycleBinProvider};
+use scan::{scan_cleanup_items_sync, scan_full_sync, scan_large_items_sync};
 use serde::{Deserialize, Serialize};
+use snapshots::{
+    diff_scans_sync, list_snapshots_sync, snapshot_dir, ScanDiff, SnapshotSummary,
+    DEFAULT_DIFF_LIMIT,
+};
+#[cfg(target_os = "windows")]
+use std::ffi::OsStr;
+#[cfg(target_os = "windows")]
+use std::os::windows::ffi::OsStrExt;
 use std::{
     collections::{HashMap, HashSet},
     env,
-    fs,
-    io::ErrorKind,
+    fs::{self, File},
+    io::{BufWriter, ErrorKind},
     path::{Path, PathBuf},
     process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
     time::{Duration, SystemTime},
 };
-#[cfg(target_os = "windows")]
-use std::ffi::OsStr;
-#[cfg(target_os = "windows")]
-use std::os::windows::ffi::OsStrExt;
-use sysinfo::Disks;
+use sysinfo::{Disk, Disks};
+use tauri::{AppHandle, Manager, State};
+use tree::{analyze_tree_sync, TreeNode, DEFAULT_TREE_DEPTH, MAX_TREE_DEPTH};
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::UI::Shell::{
-    SHEmptyRecycleBinW, SHQueryRecycleBinW, SHQUERYRBINFO, SHERB_NOCONFIRMATION,
-    SHERB_NOPROGRESSUI, SHERB_NOSOUND,
+    SHEmptyRecycleBinW, SHQueryRecycleBinW, SHERB_NOCONFIRMATION, SHERB_NOPROGRESSUI,
+    SHERB_NOSOUND, SHQUERYRBINFO,
 };
-use walkdir::WalkDir;
-use tauri::Manager;
+
+const MAX_SCAN_THREADS: usize = 16;
+const DEFAULT_SCAN_THREADS: usize = 4;
+const CANCELLED_MESSAGE: &str = "Operation cancelled.";
+const DEFAULT_DOWNLOAD_RETENTION_DAYS: u64 = 30;
+const MAX_RETENTION_DAYS: u64 = 3650;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct DiskInfo {
     mount_point: String,
+    name: String,
+    file_system: String,
+    removable: bool,
     total_bytes: u64,
     free_bytes: u64,
     used_bytes: u64,
@@ -38,7 +89,54 @@ struct CleanupCategory {
     title: String,
     description: String,
     size_bytes: u64,
+    allocated_bytes: u64,
+    reclaimable_bytes: u64,
     file_count: u64,
+    inaccessible_count: u64,
+    errors: Vec<RootScanErrors>,
+    breakdown: CategoryBreakdown,
+    retention_days: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CategoryBreakdown {
+    roots: Vec<BreakdownEntry>,
+    extensions: Vec<BreakdownEntry>,
+    ages: Vec<BreakdownEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BreakdownEntry {
+    key: String,
+    size_bytes: u64,
+    file_count: u64,
+}
+
+/// `count` covers every failure; `errors` keeps the first few.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RootScanErrors {
+    root: String,
+    count: u64,
+    errors: Vec<ScanError>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanError {
+    path: String,
+    kind: ScanErrorKind,
+    message: String,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ScanErrorKind {
+    PermissionDenied,
+    NotFound,
+    Other,
 }
 
 #[derive(Serialize)]
@@ -47,6 +145,19 @@ struct CleanupItem {
     path: String,
     size_bytes: u64,
     modified_ms: Option<i64>,
+    #[serde(flatten)]
+    details: ItemDetails,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemDetails {
+    allocated_bytes: u64,
+    created_ms: Option<i64>,
+    accessed_ms: Option<i64>,
+    owner: Option<String>,
+    read_only: bool,
+    hidden: bool,
 }
 
 #[derive(Serialize)]
@@ -63,9 +174,32 @@ struct LargeItem {
     path: String,
     name: String,
     size_bytes: u64,
+    reclaimable_bytes: u64,
+    modified_ms: Option<i64>,
     is_dir: bool,
     suspicious: bool,
+    score: u32,
+    reasons: Vec<String>,
     category_id: Option<String>,
+    #[serde(flatten)]
+    details: ItemDetails,
+    #[serde(skip)]
+    file_id: Option<FileId>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FullScan {
+    categories: Vec<CleanupCategory>,
+    large_items: Vec<LargeItem>,
+    large_item_errors: Vec<RootScanErrors>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LargeScan {
+    items: Vec<LargeItem>,
+    errors: Vec<RootScanErrors>,
 }
 
 #[derive(Serialize)]
@@ -73,6 +207,9 @@ struct LargeItem {
 struct CategoryItems {
     items: Vec<CleanupItem>,
     has_more: bool,
+    next_cursor: Option<String>,
+    total_count: u64,
+    inaccessible_count: u64,
 }
 
 #[derive(Serialize)]
@@ -88,13 +225,27 @@ struct CleanupResult {
     deleted_bytes: u64,
     deleted_count: u64,
     failed: Vec<CleanupError>,
+    cancelled: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct CategoryStats {
+struct ProgressItem {
+    path: String,
     size_bytes: u64,
-    file_count: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent {
+    operation: &'static str,
+    operation_id: Option<String>,
+    category_id: Option<String>,
+    current_root: Option<String>,
+    files: u64,
+    bytes: u64,
+    largest: Vec<ProgressItem>,
+    done: bool,
 }
 
 #[cfg(target_os = "windows")]
@@ -112,37 +263,86 @@ struct CleanRequest {
     #[serde(default)]
     included_paths: HashMap<String, Vec<String>>,
     #[serde(default)]
-    category_stats: HashMap<String, CategoryStats>,
+    options: HashMap<String, CategoryOptions>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CategoryOptions {
+    retention_days: Option<u64>,
 }
 
 #[derive(Clone)]
 enum CategoryKind {
     Standard,
-    DownloadsOlderThan { days: u64 },
+    Filtered(Vec<FilePredicate>),
 }
 
 #[derive(Clone)]
 struct CategoryDef {
-    id: &'static str,
-    title: &'static str,
-    description: &'static str,
+    id: String,
+    title: String,
+    description: String,
     kind: CategoryKind,
+    retention_days: Option<u64>,
     roots: Vec<PathBuf>,
     cleanup_dirs: bool,
+    provider: &'static dyn CleanupProvider,
+}
+
+#[cfg(test)]
+impl CategoryDef {
+    fn for_roots(id: &str, roots: Vec<PathBuf>) -> Self {
+        Self {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: String::new(),
+            kind: CategoryKind::Standard,
+            retention_days: None,
+            roots,
+            cleanup_dirs: true,
+            provider: &FileProvider,
+        }
+    }
 }
 
 #[tauri::command]
 async fn get_disk_info() -> Result<DiskInfo, String> {
     ensure_windows()?;
-    tauri::async_runtime::spawn_blocking(move || get_disk_info_sync())
+    tauri::async_runtime::spawn_blocking(get_disk_info_sync)
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn list_disks() -> Result<Vec<DiskInfo>, String> {
+    ensure_windows()?;
+    tauri::async_runtime::spawn_blocking(list_disks_sync)
         .await
         .map_err(|err| err.to_string())?
 }
 
+#[tauri::command]
+async fn get_disk_history(
+    app: AppHandle,
+    mount_point: Option<String>,
+    days: Option<u32>,
+) -> Result<DiskHistory, String> {
+    ensure_windows()?;
+    let days = days
+        .unwrap_or(DEFAULT_HISTORY_DAYS)
+        .clamp(1, DISK_HISTORY_DAYS);
+    tauri::async_runtime::spawn_blocking(move || {
+        get_disk_history_sync(&app, mount_point.as_deref(), days)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
 #[tauri::command]
 async fn get_hibernation_info() -> Result<HibernationInfo, String> {
     ensure_windows()?;
-    tauri::async_runtime::spawn_blocking(move || get_hibernation_info_sync())
+    tauri::async_runtime::spawn_blocking(get_hibernation_info_sync)
         .await
         .map_err(|err| err.to_string())?
 }
@@ -156,50 +356,324 @@ async fn set_hibernation_enabled(enabled: bool) -> Result<HibernationInfo, Strin
 }
 
 #[tauri::command]
-async fn scan_cleanup_items() -> Result<Vec<CleanupCategory>, String> {
+async fn scan_cleanup_items(
+    app: AppHandle,
+    operation_id: Option<String>,
+    threads: Option<u32>,
+    force_rescan: Option<bool>,
+    options: Option<HashMap<String, CategoryOptions>>,
+) -> Result<Vec<CleanupCategory>, String> {
     ensure_windows()?;
-    tauri::async_runtime::spawn_blocking(move || scan_cleanup_items_sync())
-        .await
-        .map_err(|err| err.to_string())?
+    let threads = scan_thread_count(threads);
+    let force_rescan = force_rescan.unwrap_or(false);
+    let profiles = app.state::<BrowserProfiles>().discover();
+    let categories = load_categories(&app, &options.unwrap_or_default(), &profiles)?;
+    let op = Operation::start(app, "scanCleanup", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = scan_cleanup_items_sync(&categories, threads, force_rescan, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn scan_large_items(
+    app: AppHandle,
+    operation_id: Option<String>,
+    limit: Option<u32>,
+    min_size_mb: Option<u64>,
+    threads: Option<u32>,
+    force_rescan: Option<bool>,
+    mount_point: Option<String>,
+    options: Option<HashMap<String, CategoryOptions>>,
+) -> Result<LargeScan, String> {
+    ensure_windows()?;
+    let limit = large_item_limit(limit);
+    let min_size_bytes = large_item_min_bytes(min_size_mb);
+    let threads = scan_thread_count(threads);
+    let force_rescan = force_rescan.unwrap_or(false);
+    let profiles = app.state::<BrowserProfiles>().discover();
+    let categories = load_categories(&app, &options.unwrap_or_default(), &profiles)?;
+    let heuristics = Heuristics::load(&app)?;
+    let op = Operation::start(app, "scanLarge", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = resolve_volume(mount_point.as_deref()).and_then(|root| {
+            let sizes = (limit, min_size_bytes);
+            scan_large_items_sync(
+                &root,
+                &categories,
+                &heuristics,
+                sizes,
+                threads,
+                force_rescan,
+                &op,
+            )
+        });
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn scan_full(
+    app: AppHandle,
+    operation_id: Option<String>,
+    limit: Option<u32>,
+    min_size_mb: Option<u64>,
+    threads: Option<u32>,
+    force_rescan: Option<bool>,
+    mount_point: Option<String>,
+    options: Option<HashMap<String, CategoryOptions>>,
+) -> Result<FullScan, String> {
+    ensure_windows()?;
+    let limit = large_item_limit(limit);
+    let min_size_bytes = large_item_min_bytes(min_size_mb);
+    let threads = scan_thread_count(threads);
+    let force_rescan = force_rescan.unwrap_or(false);
+    let profiles = app.state::<BrowserProfiles>().discover();
+    let categories = load_categories(&app, &options.unwrap_or_default(), &profiles)?;
+    let heuristics = Heuristics::load(&app)?;
+    let op = Operation::start(app, "scanFull", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = resolve_volume(mount_point.as_deref()).and_then(|root| {
+            let sizes = (limit, min_size_bytes);
+            scan_full_sync(
+                &root,
+                &categories,
+                &heuristics,
+                sizes,
+                threads,
+                force_rescan,
+                &op,
+            )
+        });
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn analyze_tree(
+    app: AppHandle,
+    operation_id: Option<String>,
+    path: String,
+    depth: Option<u32>,
+    threads: Option<u32>,
+) -> Result<TreeNode, String> {
+    ensure_windows()?;
+    let depth = depth.unwrap_or(DEFAULT_TREE_DEPTH).min(MAX_TREE_DEPTH) as usize;
+    let threads = scan_thread_count(threads);
+    let op = Operation::start(app, "analyzeTree", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = analyze_tree_sync(Path::new(&path), depth, threads, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Without a `cursor` the category is walked again; `nextCursor` continues it.
+#[tauri::command]
+async fn list_category_items(
+    app: AppHandle,
+    id: String,
+    limit: Option<u32>,
+    cursor: Option<String>,
+    query: Option<ListQuery>,
+    options: Option<CategoryOptions>,
+) -> Result<CategoryItems, String> {
+    ensure_windows()?;
+    let limit = limit.unwrap_or(200).clamp(1, 2000) as usize;
+    let query = query.unwrap_or_default();
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = HashMap::from([(id.clone(), options)]);
+        list_category_items_sync(&app.state::<Listings>(), &id, limit, cursor, &query, || {
+            let profiles = app.state::<BrowserProfiles>().current();
+            load_categories(&app, &options, &profiles)
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn scan_browser_caches(
+    app: AppHandle,
+    operation_id: Option<String>,
+    threads: Option<u32>,
+    force_rescan: Option<bool>,
+) -> Result<Vec<BrowserCache>, String> {
+    ensure_windows()?;
+    let threads = scan_thread_count(threads);
+    let force_rescan = force_rescan.unwrap_or(false);
+    let profiles = app.state::<BrowserProfiles>().discover();
+    let op = Operation::start(app, "scanBrowsers", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = scan_browser_caches_sync(&profiles, threads, force_rescan, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn clean_categories(
+    app: AppHandle,
+    operation_id: Option<String>,
+    request: CleanRequest,
+) -> Result<CleanupResult, String> {
+    ensure_windows()?;
+    let profiles = app.state::<BrowserProfiles>().current();
+    let categories = load_categories(&app, &request.options, &profiles)?;
+    let op = Operation::start(app, "cleanCategories", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = clean_categories_sync(&categories, request, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn clean_large_items(
+    app: AppHandle,
+    operation_id: Option<String>,
+    paths: Vec<String>,
+) -> Result<CleanupResult, String> {
+    ensure_windows()?;
+    let op = Operation::start(app, "cleanLarge", operation_id);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = clean_large_items_sync(paths, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+    Ok(result)
 }
 
 #[tauri::command]
-async fn scan_large_items(limit: Option<u32>, min_size_mb: Option<u64>) -> Result<Vec<LargeItem>, String> {
+async fn find_duplicates(
+    app: AppHandle,
+    operation_id: Option<String>,
+    roots: Option<Vec<String>>,
+    min_size_mb: Option<u64>,
+    threads: Option<u32>,
+) -> Result<Vec<DuplicateGroup>, String> {
     ensure_windows()?;
-    let limit = limit.unwrap_or(200).min(1000) as usize;
+    let roots = match roots {
+        Some(roots) => roots.into_iter().map(PathBuf::from).collect(),
+        None => default_duplicate_roots(),
+    };
     let min_size_bytes = min_size_mb
-        .unwrap_or(1024)
-        .saturating_mul(1024)
-        .saturating_mul(1024);
-    tauri::async_runtime::spawn_blocking(move || scan_large_items_sync(limit, min_size_bytes))
-        .await
-        .map_err(|err| err.to_string())?
+        .unwrap_or(DEFAULT_DUPLICATE_MIN_MB)
+        .saturating_mul(1024 * 1024)
+        .max(1);
+    let threads = scan_thread_count(threads);
+    let op = Operation::start(app, "findDuplicates", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = find_duplicates_sync(dedup_paths(roots), min_size_bytes, threads, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
 }
 
+/// Each copy is hashed again and only deleted while it still matches the kept one.
 #[tauri::command]
-async fn list_category_items(id: String, limit: Option<u32>) -> Result<CategoryItems, String> {
+async fn clean_duplicates(
+    app: AppHandle,
+    operation_id: Option<String>,
+    groups: Vec<DuplicateSelection>,
+) -> Result<CleanupResult, String> {
     ensure_windows()?;
-    let limit = limit.unwrap_or(200).min(2000) as usize;
-    tauri::async_runtime::spawn_blocking(move || list_category_items_sync(id, limit))
-        .await
-        .map_err(|err| err.to_string())?
+    let op = Operation::start(app, "cleanDuplicates", operation_id);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = clean_duplicates_sync(groups, &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+    Ok(result)
+}
+
+#[tauri::command]
+async fn find_empty_items(
+    app: AppHandle,
+    operation_id: Option<String>,
+    root: String,
+) -> Result<EmptyItems, String> {
+    ensure_windows()?;
+    let op = Operation::start(app, "findEmpty", operation_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = find_empty_items_sync(Path::new(&root), &guarded_dirs(), &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn clean_empty_items(
+    app: AppHandle,
+    operation_id: Option<String>,
+    root: String,
+    paths: Vec<String>,
+) -> Result<CleanupResult, String> {
+    ensure_windows()?;
+    let op = Operation::start(app, "cleanEmpty", operation_id);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = clean_empty_items_sync(Path::new(&root), paths, &guarded_dirs(), &op);
+        op.finish();
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+    Ok(result)
 }
 
 #[tauri::command]
-async fn clean_categories(request: CleanRequest) -> Result<CleanupResult, String> {
+async fn list_snapshots(app: AppHandle) -> Result<Vec<SnapshotSummary>, String> {
     ensure_windows()?;
-    tauri::async_runtime::spawn_blocking(move || clean_categories_sync(request))
+    let dir = snapshot_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || list_snapshots_sync(&dir))
         .await
-        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())
 }
 
 #[tauri::command]
-async fn clean_large_items(paths: Vec<String>) -> Result<CleanupResult, String> {
+async fn diff_scans(
+    app: AppHandle,
+    a: String,
+    b: String,
+    limit: Option<u32>,
+) -> Result<ScanDiff, String> {
     ensure_windows()?;
-    let result = tauri::async_runtime::spawn_blocking(move || clean_large_items_sync(paths))
+    let limit = limit.map_or(DEFAULT_DIFF_LIMIT, |limit| limit.max(1) as usize);
+    let dir = snapshot_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || diff_scans_sync(&dir, &a, &b, limit))
         .await
-        .map_err(|err| err.to_string())?;
-    Ok(result)
+        .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+fn cancel_operation(operations: State<'_, Operations>, operation_id: String) -> bool {
+    operations.cancel(&operation_id)
 }
 
 fn ensure_windows() -> Result<(), String> {
@@ -219,7 +693,23 @@ fn get_disk_info_sync() -> Result<DiskInfo, String> {
         .find(|disk| path_eq_ignore_case(disk.mount_point(), &mount_point))
         .or_else(|| disk_list.first())
         .ok_or_else(|| "No disks detected.".to_string())?;
+    Ok(disk_info(disk))
+}
+
+fn list_disks_sync() -> Result<Vec<DiskInfo>, String> {
+    let system_drive = system_drive_mount();
+    let disks = Disks::new_with_refreshed_list();
+    let mut infos: Vec<DiskInfo> = disks.list().iter().map(disk_info).collect();
+    infos.sort_by_key(|info| {
+        (
+            !path_eq_ignore_case(Path::new(&info.mount_point), &system_drive),
+            info.mount_point.to_lowercase(),
+        )
+    });
+    Ok(infos)
+}
 
+fn disk_info(disk: &Disk) -> DiskInfo {
     let total = disk.total_space();
     let free = disk.available_space();
     let used = total.saturating_sub(free);
@@ -229,13 +719,70 @@ fn get_disk_info_sync() -> Result<DiskInfo, String> {
         (used as f64 / total as f64) * 100.0
     };
 
-    Ok(DiskInfo {
+    DiskInfo {
         mount_point: disk.mount_point().to_string_lossy().to_string(),
+        name: disk.name().to_string_lossy().to_string(),
+        file_system: disk.file_system().to_string_lossy().to_string(),
+        removable: disk.is_removable(),
         total_bytes: total,
         free_bytes: free,
         used_bytes: used,
         used_percent,
-    })
+    }
+}
+
+/// Writes to a uniquely named temp file and renames it over `path`.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let temp_path = path.with_extension(format!(
+        "json.{}-{}.tmp",
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = File::create(&temp_path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, value).map_err(|err| err.to_string())?;
+            let file = writer.into_inner().map_err(|err| err.to_string())?;
+            file.sync_all().map_err(|err| err.to_string())
+        })
+        .and_then(|_| fs::rename(&temp_path, path).map_err(|err| err.to_string()));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written
+}
+
+fn resolve_volume(mount_point: Option<&str>) -> Result<PathBuf, String> {
+    let Some(mount_point) = mount_point else {
+        return Ok(system_drive_mount());
+    };
+    let requested = Path::new(mount_point);
+    mounted_volumes()
+        .into_iter()
+        .find(|volume| path_eq_ignore_case(volume, requested))
+        .ok_or_else(|| format!("Volume {} is not mounted.", mount_point))
+}
+
+fn mounted_volumes() -> Vec<PathBuf> {
+    Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|disk| disk.mount_point().to_path_buf())
+        .collect()
+}
+
+/// The deepest mount point wins, so a folder mount beats its drive.
+fn volume_of<'a>(volumes: &'a [PathBuf], path: &Path) -> Option<&'a Path> {
+    volumes
+        .iter()
+        .filter(|volume| is_within_root(volume, path))
+        .max_by_key(|volume| volume.as_os_str().len())
+        .map(PathBuf::as_path)
 }
 
 fn get_hibernation_info_sync() -> Result<HibernationInfo, String> {
@@ -260,121 +807,44 @@ fn set_hibernation_enabled_sync(enabled: bool) -> Result<HibernationInfo, String
         .status()
         .map_err(|err| err.to_string())?;
     if !status.success() {
-        return Err("Failed to update hibernation state. Try running as administrator.".to_string());
+        return Err(
+            "Failed to update hibernation state. Try running as administrator.".to_string(),
+        );
     }
     get_hibernation_info_sync()
 }
 
-fn scan_cleanup_items_sync() -> Result<Vec<CleanupCategory>, String> {
-    let categories = build_categories();
-    let items = categories
-        .iter()
-        .map(|def| {
-            let scan = scan_category(def);
-            CleanupCategory {
-                id: def.id.to_string(),
-                title: def.title.to_string(),
-                description: def.description.to_string(),
-                size_bytes: scan.size_bytes,
-                file_count: scan.file_count,
-            }
-        })
-        .collect();
-    Ok(items)
-}
-
-fn scan_large_items_sync(limit: usize, min_size_bytes: u64) -> Result<Vec<LargeItem>, String> {
-    let root = system_drive_mount();
-    let categories = build_categories();
-    let keywords = ["log", "cache", "temp", "tmp"];
-    let mut large_files = Vec::new();
-    let mut suspicious_dirs: HashMap<String, (PathBuf, u64)> = HashMap::new();
-
-    for entry in WalkDir::new(&root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        let metadata = match entry.metadata() {
-            Ok(value) => value,
-            Err(_) => continue,
-        };
-        let size = metadata.len();
-        let path = entry.path();
-
-        if size >= min_size_bytes {
-            let name = entry.file_name().to_string_lossy().to_string();
-            let path_text = path.to_string_lossy();
-            let suspicious =
-                contains_keyword(&name, &keywords) || contains_keyword(&path_text, &keywords);
-            let category_id = match_category_id(path, &metadata, &categories);
-            large_files.push(LargeItem {
-                path: path.to_string_lossy().to_string(),
-                name,
-                size_bytes: size,
-                is_dir: false,
-                suspicious,
-                category_id,
-            });
-        }
-
-        if let Some(suspicious_dir) = find_suspicious_dir(path.parent(), &keywords) {
-            let key = normalize_path(&suspicious_dir);
-            let entry = suspicious_dirs
-                .entry(key)
-                .or_insert((suspicious_dir, 0));
-            entry.1 = entry.1.saturating_add(size);
-        }
-    }
-
-    let mut large_dirs = Vec::new();
-    for (_, (path, size)) in suspicious_dirs {
-        if size < min_size_bytes {
-            continue;
-        }
-        let name = path
-            .file_name()
-            .map(|value| value.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.to_string_lossy().to_string());
-        large_dirs.push(LargeItem {
-            path: path.to_string_lossy().to_string(),
-            name,
-            size_bytes: size,
-            is_dir: true,
-            suspicious: true,
-            category_id: None,
-        });
+fn scan_thread_count(requested: Option<u32>) -> usize {
+    match requested {
+        Some(value) => (value as usize).clamp(1, MAX_SCAN_THREADS),
+        None => thread::available_parallelism()
+            .map(|value| value.get())
+            .unwrap_or(1)
+            .min(DEFAULT_SCAN_THREADS),
     }
+}
 
-    let mut items = Vec::with_capacity(large_files.len() + large_dirs.len());
-    items.extend(large_files);
-    items.extend(large_dirs);
-    items.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-    if items.len() > limit {
-        items.truncate(limit);
-    }
-    Ok(items)
+fn large_item_limit(requested: Option<u32>) -> usize {
+    requested.unwrap_or(200).min(1000) as usize
 }
 
-fn list_category_items_sync(id: String, limit: usize) -> Result<CategoryItems, String> {
-    let categories = build_categories();
-    let def = categories
-        .iter()
-        .find(|category| category.id == id)
-        .ok_or_else(|| "Unknown cleanup category.".to_string())?;
-    Ok(list_category_items_for(def, limit))
+fn large_item_min_bytes(min_size_mb: Option<u64>) -> u64 {
+    min_size_mb
+        .unwrap_or(1024)
+        .saturating_mul(1024)
+        .saturating_mul(1024)
 }
 
-fn clean_categories_sync(request: CleanRequest) -> Result<CleanupResult, String> {
-    let categories = build_categories();
+fn clean_categories_sync(
+    categories: &[CategoryDef],
+    request: CleanRequest,
+    op: &Operation,
+) -> Result<CleanupResult, String> {
     let CleanRequest {
         ids,
         excluded_paths,
         included_paths,
-        category_stats,
+        ..
     } = request;
     let id_set: HashSet<String> = ids.into_iter().collect();
     let mut deleted_bytes = 0;
@@ -382,26 +852,25 @@ fn clean_categories_sync(request: CleanRequest) -> Result<CleanupResult, String>
     let mut failed = Vec::new();
 
     for def in categories.iter() {
-        let included = included_paths
-            .get(def.id)
-            .cloned()
-            .unwrap_or_default();
+        if op.is_cancelled() {
+            break;
+        }
+        let included = included_paths.get(&def.id).cloned().unwrap_or_default();
         if !included.is_empty() {
-            let result = clean_included_paths(def, &included);
+            let result = def.provider.clean_paths(def, &included, op);
             deleted_bytes += result.deleted_bytes;
             deleted_count += result.deleted_count;
             failed.extend(result.failed);
             continue;
         }
-        if !id_set.contains(def.id) {
+        if !id_set.contains(&def.id) {
             continue;
         }
         let excluded = excluded_paths
-            .get(def.id)
-            .map(normalize_exclusions)
+            .get(&def.id)
+            .map(|exclusions| normalize_exclusions(exclusions))
             .unwrap_or_default();
-        let stats = category_stats.get(def.id);
-        let result = clean_category(def, &excluded, stats);
+        let result = def.provider.clean(def, &excluded, op);
         deleted_bytes += result.deleted_bytes;
         deleted_count += result.deleted_count;
         failed.extend(result.failed);
@@ -411,344 +880,113 @@ fn clean_categories_sync(request: CleanRequest) -> Result<CleanupResult, String>
         deleted_bytes,
         deleted_count,
         failed,
+        cancelled: op.is_cancelled(),
     })
 }
 
-fn clean_large_items_sync(paths: Vec<String>) -> CleanupResult {
-    let root = system_drive_mount();
+fn clean_large_items_sync(paths: Vec<String>, op: &Operation) -> CleanupResult {
+    let volumes = mounted_volumes();
     let mut deleted_bytes: u64 = 0;
     let mut deleted_count: u64 = 0;
     let mut failed = Vec::new();
     let mut seen = HashSet::new();
 
     for path_str in paths {
+        if op.is_cancelled() {
+            break;
+        }
         let normalized = normalize_path_str(&path_str);
         if !seen.insert(normalized) {
             continue;
         }
         let path = Path::new(&path_str);
-        if !is_within_root(&root, path) {
+        let Some(root) = volume_of(&volumes, path) else {
             failed.push(CleanupError {
                 path: path_str.clone(),
-                message: "Path is outside scan scope.".to_string(),
+                message: "Path is not on a mounted volume.".to_string(),
             });
             continue;
-        }
-        if path_eq_ignore_case(path, &root) {
-            failed.push(CleanupError {
-                path: path_str.clone(),
-                message: "Refusing to delete drive root.".to_string(),
-            });
-            continue;
-        }
-        let metadata = match path.metadata() {
-            Ok(meta) => meta,
-            Err(err) => {
-                failed.push(CleanupError {
-                    path: path_str.clone(),
-                    message: err.to_string(),
-                });
-                continue;
-            }
         };
-        if metadata.is_dir() {
-            let (size, count) = dir_metrics(path);
-            if let Err(err) = fs::remove_dir_all(path) {
-                failed.push(CleanupError {
-                    path: path_str.clone(),
-                    message: err.to_string(),
-                });
-                continue;
-            }
-            deleted_bytes = deleted_bytes.saturating_add(size);
-            deleted_count = deleted_count.saturating_add(count);
-        } else {
-            let size = metadata.len();
-            if let Err(err) = fs::remove_file(path) {
-                failed.push(CleanupError {
-                    path: path_str.clone(),
-                    message: err.to_string(),
-                });
-                continue;
-            }
-            deleted_bytes = deleted_bytes.saturating_add(size);
-            deleted_count = deleted_count.saturating_add(1);
-        }
+        op.progress.enter(None, path);
+        delete_selected_path(
+            path,
+            root,
+            &mut deleted_bytes,
+            &mut deleted_count,
+            &mut failed,
+            op,
+        );
     }
 
     CleanupResult {
         deleted_bytes,
         deleted_count,
         failed,
+        cancelled: op.is_cancelled(),
     }
 }
 
-struct CategoryScan {
-    size_bytes: u64,
-    file_count: u64,
-}
-
-fn scan_category(def: &CategoryDef) -> CategoryScan {
-    let cutoff = cutoff_time(&def.kind);
-    let mut size_bytes = 0;
-    let mut file_count = 0;
-
-    for root in &def.roots {
-        size_bytes += scan_root(root, cutoff, &mut file_count);
-    }
-
-    CategoryScan {
-        size_bytes,
-        file_count,
-    }
-}
-
-fn scan_root(root: &Path, cutoff: Option<SystemTime>, file_count: &mut u64) -> u64 {
-    if !root.exists() {
-        return 0;
-    }
-
-    let mut size_bytes = 0;
-    if root.is_file() {
-        if let Ok(metadata) = root.metadata() {
-            if matches_cutoff(&metadata, cutoff) {
-                size_bytes += metadata.len();
-                *file_count += 1;
-            }
-        }
-        return size_bytes;
-    }
-
-    for entry in WalkDir::new(root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        if let Ok(metadata) = entry.metadata() {
-            if matches_cutoff(&metadata, cutoff) {
-                size_bytes += metadata.len();
-                *file_count += 1;
-            }
-        }
-    }
-
-    size_bytes
-}
-
-fn list_category_items_for(def: &CategoryDef, limit: usize) -> CategoryItems {
-    let cutoff = cutoff_time(&def.kind);
-    let mut items = Vec::new();
-    let mut has_more = false;
-
-    for root in &def.roots {
-        if items.len() >= limit {
-            has_more = true;
-            break;
-        }
-        if !root.exists() {
-            continue;
-        }
-        if root.is_file() {
-            if let Ok(metadata) = root.metadata() {
-                if matches_cutoff(&metadata, cutoff) {
-                    items.push(to_item(root, &metadata));
-                }
-            }
-            continue;
-        }
-
-        for entry in WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|entry| entry.ok())
-        {
-            if items.len() >= limit {
-                has_more = true;
-                break;
-            }
-            if !entry.file_type().is_file() {
-                continue;
-            }
-            if let Ok(metadata) = entry.metadata() {
-                if matches_cutoff(&metadata, cutoff) {
-                    items.push(to_item(entry.path(), &metadata));
-                }
-            }
-        }
-    }
-
-    CategoryItems { items, has_more }
-}
-
-fn clean_category(
-    def: &CategoryDef,
-    excluded: &HashSet<String>,
-    stats: Option<&CategoryStats>,
-) -> CleanupResult {
-    if def.id == "recycle_bin" && excluded.is_empty() {
-        return clean_recycle_bin_fast();
-    }
-    if excluded.is_empty() && should_fast_clear(def) {
-        return clean_category_fast_dirs(def, stats);
-    }
-    let cutoff = cutoff_time(&def.kind);
-    let mut deleted_bytes = 0;
-    let mut deleted_count = 0;
-    let mut failed = Vec::new();
-    let mut dirs = Vec::new();
-
-    for root in &def.roots {
-        if !root.exists() {
-            continue;
-        }
-        if root.is_file() {
-            delete_file(root, cutoff, excluded, &mut deleted_bytes, &mut deleted_count, &mut failed);
-            continue;
-        }
-
-        for entry in WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|entry| entry.ok())
-        {
-            if entry.file_type().is_dir() {
-                if def.cleanup_dirs {
-                    dirs.push(entry.path().to_path_buf());
-                }
-                continue;
-            }
-            if entry.file_type().is_file() {
-                delete_file(
-                    entry.path(),
-                    cutoff,
-                    excluded,
-                    &mut deleted_bytes,
-                    &mut deleted_count,
-                    &mut failed,
-                );
-            }
-        }
-
-        if def.cleanup_dirs {
-            dirs.push(root.to_path_buf());
-        }
-    }
-
-    if def.cleanup_dirs && !dirs.is_empty() {
-        dirs.sort_by(|a, b| b.components().count().cmp(&a.components().count()));
-        for dir in dirs {
-            let _ = fs::remove_dir(&dir);
-        }
+fn delete_selected_path(
+    path: &Path,
+    root: &Path,
+    deleted_bytes: &mut u64,
+    deleted_count: &mut u64,
+    failed: &mut Vec<CleanupError>,
+    op: &Operation,
+) {
+    let path_str = path.to_string_lossy().to_string();
+    if !is_within_root(root, path) {
+        failed.push(CleanupError {
+            path: path_str,
+            message: "Path is outside scan scope.".to_string(),
+        });
+        return;
     }
-
-    CleanupResult {
-        deleted_bytes,
-        deleted_count,
-        failed,
+    if path_eq_ignore_case(path, root) {
+        failed.push(CleanupError {
+            path: path_str,
+            message: "Refusing to delete drive root.".to_string(),
+        });
+        return;
     }
-}
-
-fn clean_included_paths(def: &CategoryDef, included: &[String]) -> CleanupResult {
-    let cutoff = cutoff_time(&def.kind);
-    let mut deleted_bytes = 0;
-    let mut deleted_count = 0;
-    let mut failed = Vec::new();
-    let mut seen = HashSet::new();
-
-    for path_str in included {
-        let normalized = normalize_path_str(path_str);
-        if !seen.insert(normalized) {
-            continue;
-        }
-        let path = Path::new(path_str);
-        if !is_within_roots(def, path) {
-            failed.push(CleanupError {
-                path: path_str.clone(),
-                message: "Path is outside cleanup scope.".to_string(),
-            });
-            continue;
-        }
-        let metadata = match path.metadata() {
-            Ok(meta) => meta,
-            Err(err) => {
-                failed.push(CleanupError {
-                    path: path_str.clone(),
-                    message: err.to_string(),
-                });
-                continue;
-            }
-        };
-        if metadata.is_dir() {
+    let metadata = match path.metadata() {
+        Ok(meta) => meta,
+        Err(err) => {
             failed.push(CleanupError {
-                path: path_str.clone(),
-                message: "Path is a directory.".to_string(),
+                path: path_str,
+                message: err.to_string(),
             });
-            continue;
-        }
-        if !matches_cutoff(&metadata, cutoff) {
-            continue;
+            return;
         }
-        let size = metadata.len();
+    };
+    if metadata.is_dir() {
+        let result = remove_tree(path, op);
+        *deleted_bytes = deleted_bytes.saturating_add(result.deleted_bytes);
+        *deleted_count = deleted_count.saturating_add(result.deleted_count);
+        failed.extend(result.failed);
+    } else {
+        let size = reclaimed_size(path, &metadata);
         if let Err(err) = fs::remove_file(path) {
             failed.push(CleanupError {
-                path: path_str.clone(),
+                path: path_str,
                 message: err.to_string(),
             });
-            continue;
+            return;
         }
-        deleted_bytes += size;
-        deleted_count += 1;
-    }
-
-    CleanupResult {
-        deleted_bytes,
-        deleted_count,
-        failed,
+        *deleted_bytes = deleted_bytes.saturating_add(size);
+        *deleted_count = deleted_count.saturating_add(1);
+        op.progress.add_file(path, size);
     }
 }
 
-fn should_fast_clear(def: &CategoryDef) -> bool {
-    matches!(def.id, "system_cache" | "browser_cache") && def.cleanup_dirs
-}
-
-fn clean_category_fast_dirs(def: &CategoryDef, stats: Option<&CategoryStats>) -> CleanupResult {
-    let mut failed = Vec::new();
-    let mut all_ok = true;
-
-    for root in &def.roots {
-        if !root.exists() {
-            continue;
-        }
-        let result = if root.is_file() {
-            fs::remove_file(root).map_err(|err| err.to_string())
-        } else {
-            fs::remove_dir_all(root).map_err(|err| err.to_string())
-        };
-        if let Err(message) = result {
-            all_ok = false;
-            failed.push(CleanupError {
-                path: root.to_string_lossy().to_string(),
-                message,
-            });
+impl From<ErrorKind> for ScanErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            ErrorKind::NotFound => ScanErrorKind::NotFound,
+            _ => ScanErrorKind::Other,
         }
     }
-
-    let (deleted_bytes, deleted_count) = if all_ok {
-        stats
-            .map(|value| (value.size_bytes, value.file_count))
-            .unwrap_or((0, 0))
-    } else {
-        (0, 0)
-    };
-
-    CleanupResult {
-        deleted_bytes,
-        deleted_count,
-        failed,
-    }
 }
 
 fn clean_recycle_bin_fast() -> CleanupResult {
@@ -768,12 +1006,14 @@ fn clean_recycle_bin_fast() -> CleanupResult {
                 deleted_bytes: 0,
                 deleted_count: 0,
                 failed,
+                cancelled: false,
             };
         }
         CleanupResult {
             deleted_bytes: stats.deleted_bytes,
             deleted_count: stats.deleted_count,
             failed,
+            cancelled: false,
         }
     }
     #[cfg(not(target_os = "windows"))]
@@ -785,6 +1025,7 @@ fn clean_recycle_bin_fast() -> CleanupResult {
                 path: "$Recycle.Bin".to_string(),
                 message: "Recycle bin fast clear is only supported on Windows.".to_string(),
             }],
+            cancelled: false,
         }
     }
 }
@@ -850,11 +1091,12 @@ fn is_within_roots(def: &CategoryDef, path: &Path) -> bool {
 
 fn delete_file(
     path: &Path,
-    cutoff: Option<SystemTime>,
+    cutoff: Option<Cutoff>,
     excluded: &HashSet<String>,
     deleted_bytes: &mut u64,
     deleted_count: &mut u64,
     failed: &mut Vec<CleanupError>,
+    op: &Operation,
 ) {
     let normalized = normalize_path(path);
     if excluded.contains(&normalized) {
@@ -873,7 +1115,7 @@ fn delete_file(
     if !matches_cutoff(&metadata, cutoff) {
         return;
     }
-    let size = metadata.len();
+    let size = reclaimed_size(path, &metadata);
     if let Err(err) = fs::remove_file(path) {
         failed.push(CleanupError {
             path: path.to_string_lossy().to_string(),
@@ -883,43 +1125,49 @@ fn delete_file(
     }
     *deleted_bytes += size;
     *deleted_count += 1;
+    op.progress.add_file(path, size);
+}
+
+fn walk_failure(err: &walkdir::Error, root: &Path) -> CleanupError {
+    CleanupError {
+        path: err.path().unwrap_or(root).to_string_lossy().to_string(),
+        message: err.to_string(),
+    }
 }
 
 fn to_item(path: &Path, metadata: &fs::Metadata) -> CleanupItem {
-    let modified_ms = metadata
-        .modified()
-        .ok()
-        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
-        .map(|duration| duration.as_millis() as i64);
+    let modified_ms = metadata.modified().ok().and_then(to_unix_ms);
 
     CleanupItem {
         path: path.to_string_lossy().to_string(),
         size_bytes: metadata.len(),
         modified_ms,
+        details: ItemDetails::read(path, metadata),
     }
 }
 
-fn matches_cutoff(metadata: &fs::Metadata, cutoff: Option<SystemTime>) -> bool {
-    match cutoff {
-        Some(cutoff) => metadata
-            .modified()
-            .ok()
-            .map(|modified| modified < cutoff)
-            .unwrap_or(false),
-        None => true,
-    }
+fn to_unix_ms(time: SystemTime) -> Option<i64> {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_millis() as i64)
 }
 
-fn cutoff_time(kind: &CategoryKind) -> Option<SystemTime> {
+fn from_unix_ms(ms: i64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(ms.max(0) as u64)
+}
+
+fn matches_cutoff(metadata: &fs::Metadata, cutoff: Option<Cutoff>) -> bool {
+    cutoff.is_none_or(|cutoff| cutoff.matches(&FileStat::of(metadata)))
+}
+
+fn cutoff_time(kind: &CategoryKind) -> Option<Cutoff> {
     match kind {
         CategoryKind::Standard => None,
-        CategoryKind::DownloadsOlderThan { days } => SystemTime::now()
-            .checked_sub(Duration::from_secs(days.saturating_mul(86_400)))
-            .or(Some(SystemTime::UNIX_EPOCH)),
+        CategoryKind::Filtered(predicates) => Some(Cutoff::new(predicates)),
     }
 }
 
-fn normalize_exclusions(exclusions: &Vec<String>) -> HashSet<String> {
+fn normalize_exclusions(exclusions: &[String]) -> HashSet<String> {
     exclusions
         .iter()
         .map(|path| normalize_path_str(path))
@@ -948,65 +1196,28 @@ fn is_within_root(root: &Path, path: &Path) -> bool {
     target == prefix.trim_end_matches('\\') || target.starts_with(&prefix)
 }
 
-fn contains_keyword(text: &str, keywords: &[&str]) -> bool {
-    let lowered = text.to_lowercase();
-    keywords.iter().any(|keyword| lowered.contains(keyword))
-}
-
-fn find_suspicious_dir(path: Option<&Path>, keywords: &[&str]) -> Option<PathBuf> {
-    let mut current = path?;
-    loop {
-        if let Some(name) = current.file_name().and_then(|value| value.to_str()) {
-            if contains_keyword(name, keywords) {
-                return Some(current.to_path_buf());
-            }
-        }
-        current = current.parent()?;
-    }
-}
-
-fn match_category_id(
-    path: &Path,
-    metadata: &fs::Metadata,
-    categories: &[CategoryDef],
-) -> Option<String> {
+fn match_category_id(path: &Path, file: &FileStat, categories: &[CategoryDef]) -> Option<String> {
     for def in categories {
         if !is_within_roots(def, path) {
             continue;
         }
         let cutoff = cutoff_time(&def.kind);
-        if !matches_cutoff(metadata, cutoff) {
+        if !cutoff.is_none_or(|cutoff| cutoff.matches(file)) {
             continue;
         }
-        return Some(def.id.to_string());
+        return Some(def.id.clone());
     }
     None
 }
 
-fn dir_metrics(path: &Path) -> (u64, u64) {
-    let mut size: u64 = 0;
-    let mut count: u64 = 0;
-    for entry in WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        if let Ok(metadata) = entry.metadata() {
-            size = size.saturating_add(metadata.len());
-            count = count.saturating_add(1);
-        }
-    }
-    (size, count)
-}
-
 fn path_eq_ignore_case(left: &Path, right: &Path) -> bool {
     normalize_path(left) == normalize_path(right)
 }
 
-fn build_categories() -> Vec<CategoryDef> {
+fn build_categories(
+    options: &HashMap<String, CategoryOptions>,
+    browser_profiles: &[BrowserProfile],
+) -> Vec<CategoryDef> {
     let system_drive = env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
     let system_root = env::var("SystemRoot")
         .map(PathBuf::from)
@@ -1022,33 +1233,10 @@ fn build_categories() -> Vec<CategoryDef> {
 
     let mut browser_paths = Vec::new();
     if let Some(local) = &local_app_data {
-        browser_paths.extend([
-            local.join("Microsoft").join("Windows").join("INetCache"),
-            local
-                .join("Google")
-                .join("Chrome")
-                .join("User Data")
-                .join("Default")
-                .join("Cache"),
-            local
-                .join("Google")
-                .join("Chrome")
-                .join("User Data")
-                .join("Default")
-                .join("Code Cache"),
-            local
-                .join("Microsoft")
-                .join("Edge")
-                .join("User Data")
-                .join("Default")
-                .join("Cache"),
-            local
-                .join("Microsoft")
-                .join("Edge")
-                .join("User Data")
-                .join("Default")
-                .join("Code Cache"),
-        ]);
+        browser_paths.push(local.join("Microsoft").join("Windows").join("INetCache"));
+    }
+    for profile in browser_profiles {
+        browser_paths.extend(profile.caches.iter().map(|(_, path)| path.clone()));
     }
 
     let recycle_bins = {
@@ -1065,6 +1253,7 @@ fn build_categories() -> Vec<CategoryDef> {
     };
     let windows_old = PathBuf::from(format!("{}\\Windows.old", system_drive));
 
+    let download_days = retention_days(options, "downloads_old", DEFAULT_DOWNLOAD_RETENTION_DAYS);
     let download_root = user_profile
         .as_ref()
         .map(|profile| profile.join("Downloads"))
@@ -1073,72 +1262,86 @@ fn build_categories() -> Vec<CategoryDef> {
 
     vec![
         CategoryDef {
-            id: "temp_files",
-            title: "临时文件",
-            description: "Windows 和应用程序创建的临时文件",
+            id: "temp_files".to_string(),
+            title: "临时文件".to_string(),
+            description: "Windows 和应用程序创建的临时文件".to_string(),
             kind: CategoryKind::Standard,
+            retention_days: None,
             roots: dedup_paths(temp_paths),
             cleanup_dirs: true,
+            provider: &FileProvider,
         },
         CategoryDef {
-            id: "recycle_bin",
-            title: "回收站",
-            description: "清空回收站中的所有文件",
+            id: "recycle_bin".to_string(),
+            title: "回收站".to_string(),
+            description: "清空回收站中的所有文件".to_string(),
             kind: CategoryKind::Standard,
+            retention_days: None,
             roots: dedup_paths(recycle_bins),
             cleanup_dirs: true,
+            provider: &RecycleBinProvider,
         },
         CategoryDef {
-            id: "downloads_old",
-            title: "下载文件夹",
-            description: "清理超过30天的下载文件",
-            kind: CategoryKind::DownloadsOlderThan { days: 30 },
+            id: "downloads_old".to_string(),
+            title: "下载文件夹".to_string(),
+            description: format!("清理超过{}天的下载文件", download_days),
+            kind: CategoryKind::Filtered(vec![FilePredicate::ModifiedOlderThan {
+                days: download_days,
+            }]),
+            retention_days: Some(download_days),
             roots: dedup_paths(download_root),
             cleanup_dirs: false,
+            provider: &FileProvider,
         },
         CategoryDef {
-            id: "system_cache",
-            title: "系统缓存",
-            description: "Windows 更新和系统缓存文件",
+            id: "system_cache".to_string(),
+            title: "系统缓存".to_string(),
+            description: "Windows 更新和系统缓存文件".to_string(),
             kind: CategoryKind::Standard,
+            retention_days: None,
             roots: dedup_paths(vec![
-                system_root
-                    .join("SoftwareDistribution")
-                    .join("Download"),
+                system_root.join("SoftwareDistribution").join("Download"),
                 system_root
                     .join("SoftwareDistribution")
                     .join("DeliveryOptimization")
                     .join("Cache"),
             ]),
             cleanup_dirs: true,
+            provider: &CacheProvider,
         },
         CategoryDef {
-            id: "browser_cache",
-            title: "浏览器缓存",
-            description: "清理浏览器缓存和 Cookie",
+            id: "browser_cache".to_string(),
+            title: "浏览器缓存".to_string(),
+            description: "清理浏览器缓存和 Cookie".to_string(),
             kind: CategoryKind::Standard,
+            retention_days: None,
             roots: dedup_paths(browser_paths),
             cleanup_dirs: true,
+            provider: &CacheProvider,
         },
         CategoryDef {
-            id: "system_logs",
-            title: "系统日志",
-            description: "Windows 事件日志和应用日志",
+            id: "system_logs".to_string(),
+            title: "系统日志".to_string(),
+            description: "Windows 事件日志和应用日志".to_string(),
             kind: CategoryKind::Standard,
+            retention_days: None,
             roots: dedup_paths(vec![
                 system_root.join("Logs"),
                 system_root.join("System32").join("LogFiles"),
                 system_root.join("Panther"),
             ]),
             cleanup_dirs: true,
+            provider: &FileProvider,
         },
         CategoryDef {
-            id: "windows_old",
-            title: "旧 Windows 版本",
-            description: "Windows 更新后保留的旧系统文件",
+            id: "windows_old".to_string(),
+            title: "旧 Windows 版本".to_string(),
+            description: "Windows 更新后保留的旧系统文件".to_string(),
             kind: CategoryKind::Standard,
+            retention_days: None,
             roots: dedup_paths(vec![windows_old]),
             cleanup_dirs: true,
+            provider: &FileProvider,
         },
     ]
 }
@@ -1155,6 +1358,14 @@ fn dedup_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
     output
 }
 
+fn retention_days(options: &HashMap<String, CategoryOptions>, id: &str, default: u64) -> u64 {
+    options
+        .get(id)
+        .and_then(|options| options.retention_days)
+        .unwrap_or(default)
+        .clamp(1, MAX_RETENTION_DAYS)
+}
+
 fn system_drive_mount() -> PathBuf {
     let drive = env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
     PathBuf::from(format!("{}\\", drive))
@@ -1171,20 +1382,145 @@ pub fn run() {
                     let _ = window.set_icon(icon);
                 }
             }
+            start_disk_sampler(app.handle().clone());
             Ok(())
         })
+        .manage(Operations::default())
+        .manage(Listings::default())
+        .manage(BrowserProfiles::default())
+        .manage(DiskHistoryLock::default())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
             get_disk_info,
+            list_disks,
+            get_disk_history,
             get_hibernation_info,
             set_hibernation_enabled,
             scan_cleanup_items,
+            scan_browser_caches,
             scan_large_items,
+            scan_full,
+            analyze_tree,
             list_category_items,
             clean_categories,
-            clean_large_items
+            clean_large_items,
+            find_duplicates,
+            clean_duplicates,
+            find_empty_items,
+            clean_empty_items,
+            list_snapshots,
+            diff_scans,
+            cancel_operation
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_resolve_to_their_deepest_mounted_volume() {
+        let volumes = [
+            PathBuf::from("C:\\"),
+            PathBuf::from("D:\\"),
+            PathBuf::from("D:\\Mounts\\Archive"),
+        ];
+        let volume = |path: &str| volume_of(&volumes, Path::new(path));
+
+        assert_eq!(volume("c:\\Users\\me\\a.iso"), Some(Path::new("C:\\")));
+        assert_eq!(volume("D:\\Games\\big.pak"), Some(Path::new("D:\\")));
+        assert_eq!(
+            volume("d:\\mounts\\archive\\2020.zip"),
+            Some(Path::new("D:\\Mounts\\Archive"))
+        );
+        assert_eq!(volume("D:\\MountsArchive\\x"), Some(Path::new("D:\\")));
+        assert_eq!(volume("E:\\data.bin"), None);
+    }
+
+    #[test]
+    fn deletion_stays_inside_the_selected_volume() {
+        let volume = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let inside = volume.path().join("big.bin");
+        let outside = other.path().join("big.bin");
+        fs::write(&inside, [0; 100]).unwrap();
+        fs::write(&outside, [0; 100]).unwrap();
+
+        let op = Operation::detached("test");
+        let (mut deleted_bytes, mut deleted_count, mut failed) = (0, 0, Vec::new());
+        for path in [outside.as_path(), volume.path(), inside.as_path()] {
+            delete_selected_path(
+                path,
+                volume.path(),
+                &mut deleted_bytes,
+                &mut deleted_count,
+                &mut failed,
+                &op,
+            );
+        }
+        assert_eq!((deleted_bytes, deleted_count), (100, 1));
+        let messages: Vec<&str> = failed.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Path is outside scan scope.",
+                "Refusing to delete drive root."
+            ]
+        );
+        assert!(outside.exists() && !inside.exists());
+    }
+
+    fn downloads(retention_days: Option<u64>) -> CategoryDef {
+        let options = HashMap::from([(
+            "downloads_old".to_string(),
+            CategoryOptions { retention_days },
+        )]);
+        build_categories(&options, &[])
+            .into_iter()
+            .find(|def| def.id == "downloads_old")
+            .unwrap()
+    }
+
+    #[test]
+    fn retention_days_shape_the_description_and_are_clamped() {
+        let week = downloads(Some(7));
+        assert_eq!(week.retention_days, Some(7));
+        assert_eq!(week.description, "清理超过7天的下载文件");
+        assert_eq!(
+            downloads(None).retention_days,
+            Some(DEFAULT_DOWNLOAD_RETENTION_DAYS)
+        );
+        assert_eq!(downloads(Some(0)).retention_days, Some(1));
+        assert_eq!(
+            downloads(Some(u64::MAX)).retention_days,
+            Some(MAX_RETENTION_DAYS)
+        );
+    }
+
+    #[test]
+    fn scans_and_listings_share_the_retention_cutoff() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, days) in [("new.zip", 3), ("old.zip", 10)] {
+            let path = dir.path().join(name);
+            fs::write(&path, [0; 100]).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(cutoff::days_ago(days)))
+                .unwrap();
+        }
+        let categories = vec![CategoryDef {
+            roots: vec![dir.path().to_path_buf()],
+            ..downloads(Some(7))
+        }];
+
+        let op = Operation::detached("test");
+        let scanned = scan_cleanup_items_sync(&categories, 1, false, &op).unwrap();
+        assert_eq!((scanned[0].size_bytes, scanned[0].file_count), (100, 1));
+        let (listed, _) = categories[0].provider.list(&categories[0]);
+        assert_eq!(listed.len(), 1);
+    }
+}
@@ -0,0 +1,471 @@
+use crate::{
+    cutoff_time, matches_cutoff, to_item, to_unix_ms, CategoryDef, CategoryItems, CleanupItem,
+    ItemDetails,
+};
+use serde::Deserialize;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListQuery {
+    sort: Option<ListSort>,
+    order: Option<SortOrder>,
+    min_size_bytes: Option<u64>,
+    older_than_days: Option<u64>,
+    newer_than_days: Option<u64>,
+    extensions: Option<Vec<String>>,
+    name_contains: Option<String>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum ListSort {
+    Size,
+    Modified,
+    Name,
+    Extension,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Last listing per category, so later pages skip the walk.
+#[derive(Default)]
+pub(crate) struct Listings(Mutex<ListingCache>);
+
+#[derive(Default)]
+struct ListingCache {
+    next_id: u64,
+    listings: HashMap<String, Arc<CategoryListing>>,
+}
+
+struct CategoryListing {
+    id: u64,
+    files: Vec<ListedFile>,
+    inaccessible_count: u64,
+}
+
+pub(crate) struct ListedFile {
+    path: PathBuf,
+    size_bytes: u64,
+    modified_ms: Option<i64>,
+}
+
+impl Listings {
+    fn insert(
+        &self,
+        category_id: &str,
+        files: Vec<ListedFile>,
+        inaccessible_count: u64,
+    ) -> Arc<CategoryListing> {
+        let mut cache = self.lock();
+        cache.next_id += 1;
+        let listing = Arc::new(CategoryListing {
+            id: cache.next_id,
+            files,
+            inaccessible_count,
+        });
+        cache
+            .listings
+            .insert(category_id.to_string(), listing.clone());
+        listing
+    }
+
+    fn get(&self, category_id: &str, listing_id: u64) -> Option<Arc<CategoryListing>> {
+        self.lock()
+            .listings
+            .get(category_id)
+            .filter(|listing| listing.id == listing_id)
+            .cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ListingCache> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// `categories` is only loaded when a new listing has to be walked.
+pub(crate) fn list_category_items_sync(
+    listings: &Listings,
+    id: &str,
+    limit: usize,
+    cursor: Option<String>,
+    query: &ListQuery,
+    categories: impl FnOnce() -> Result<Vec<CategoryDef>, String>,
+) -> Result<CategoryItems, String> {
+    let (listing, offset) = match cursor {
+        Some(cursor) => {
+            let (listing_id, offset) =
+                parse_cursor(&cursor).ok_or_else(|| "Invalid cursor.".to_string())?;
+            let listing = listings
+                .get(id, listing_id)
+                .ok_or_else(|| "Listing has expired. Reload the category.".to_string())?;
+            (listing, offset)
+        }
+        None => {
+            let categories = categories()?;
+            let def = categories
+                .iter()
+                .find(|category| category.id == id)
+                .ok_or_else(|| "Unknown cleanup category.".to_string())?;
+            let (mut files, inaccessible_count) = def.provider.list(def);
+            query.apply(&mut files);
+            (listings.insert(id, files, inaccessible_count), 0)
+        }
+    };
+
+    let start = offset.min(listing.files.len());
+    let end = start.saturating_add(limit).min(listing.files.len());
+    let items = listing.files[start..end]
+        .iter()
+        .map(|file| match fs::symlink_metadata(&file.path) {
+            Ok(metadata) => to_item(&file.path, &metadata),
+            Err(_) => CleanupItem {
+                path: file.path.to_string_lossy().to_string(),
+                size_bytes: file.size_bytes,
+                modified_ms: file.modified_ms,
+                details: ItemDetails::default(),
+            },
+        })
+        .collect();
+    let has_more = end < listing.files.len();
+    Ok(CategoryItems {
+        items,
+        has_more,
+        next_cursor: has_more.then(|| format!("{}:{}", listing.id, end)),
+        total_count: listing.files.len() as u64,
+        inaccessible_count: listing.inaccessible_count,
+    })
+}
+
+fn parse_cursor(cursor: &str) -> Option<(u64, usize)> {
+    let (listing_id, offset) = cursor.split_once(':')?;
+    Some((listing_id.parse().ok()?, offset.parse().ok()?))
+}
+
+pub(crate) fn list_category_files(def: &CategoryDef) -> (Vec<ListedFile>, u64) {
+    let cutoff = cutoff_time(&def.kind);
+    let mut files = Vec::new();
+    let mut inaccessible_count = 0;
+
+    for root in &def.roots {
+        if !root.exists() {
+            continue;
+        }
+        if root.is_file() {
+            match root.metadata() {
+                Ok(metadata) => {
+                    if matches_cutoff(&metadata, cutoff) {
+                        files.push(ListedFile::new(root, &metadata));
+                    }
+                }
+                Err(_) => inaccessible_count += 1,
+            }
+            continue;
+        }
+
+        for entry in WalkDir::new(root).follow_links(false) {
+            let Ok(entry) = entry else {
+                inaccessible_count += 1;
+                continue;
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            match entry.metadata() {
+                Ok(metadata) => {
+                    if matches_cutoff(&metadata, cutoff) {
+                        files.push(ListedFile::new(entry.path(), &metadata));
+                    }
+                }
+                Err(_) => inaccessible_count += 1,
+            }
+        }
+    }
+
+    (files, inaccessible_count)
+}
+
+impl ListedFile {
+    fn new(path: &Path, metadata: &fs::Metadata) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            size_bytes: metadata.len(),
+            modified_ms: metadata.modified().ok().and_then(to_unix_ms),
+        }
+    }
+
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    fn extension(&self) -> String {
+        self.path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+impl ListQuery {
+    /// Size sorts largest first and modified oldest first unless `order` says otherwise.
+    fn apply(&self, files: &mut Vec<ListedFile>) {
+        let now_ms = to_unix_ms(SystemTime::now()).unwrap_or(0);
+        let days_ago = |days: u64| {
+            i64::try_from(days)
+                .ok()
+                .and_then(|days| days.checked_mul(86_400_000))
+                .map_or(i64::MIN, |ms| now_ms.saturating_sub(ms))
+        };
+        let older_than = self.older_than_days.map(days_ago);
+        let newer_than = self.newer_than_days.map(days_ago);
+        let extensions: Option<HashSet<String>> = self.extensions.as_ref().map(|list| {
+            list.iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .collect()
+        });
+        let name_contains = self
+            .name_contains
+            .as_ref()
+            .map(|text| text.trim().to_lowercase())
+            .filter(|text| !text.is_empty());
+
+        files.retain(|file| {
+            if file.size_bytes < self.min_size_bytes.unwrap_or(0) {
+                return false;
+            }
+            if let Some(cutoff) = older_than {
+                if file.modified_ms.is_none_or(|modified| modified >= cutoff) {
+                    return false;
+                }
+            }
+            if let Some(cutoff) = newer_than {
+                if file.modified_ms.is_none_or(|modified| modified < cutoff) {
+                    return false;
+                }
+            }
+            if let Some(extensions) = &extensions {
+                if !extensions.contains(&file.extension()) {
+                    return false;
+                }
+            }
+            if let Some(text) = &name_contains {
+                if !file.name().contains(text.as_str()) {
+                    return false;
+                }
+            }
+            true
+        });
+
+        let Some(sort) = self.sort else {
+            return;
+        };
+        let descending = match self.order {
+            Some(order) => order == SortOrder::Desc,
+            None => matches!(sort, ListSort::Size),
+        };
+        match sort {
+            ListSort::Size => sort_listed(files, descending, |file| Some(file.size_bytes)),
+            ListSort::Modified => sort_listed(files, descending, |file| file.modified_ms),
+            ListSort::Name => sort_listed(files, descending, |file| Some(file.name())),
+            ListSort::Extension => sort_listed(files, descending, |file| {
+                Some((file.extension(), file.name()))
+            }),
+        }
+    }
+}
+
+/// Unknown keys go last either way; ties keep path order.
+fn sort_listed<K: Ord>(
+    files: &mut [ListedFile],
+    descending: bool,
+    key: impl Fn(&ListedFile) -> Option<K>,
+) {
+    if descending {
+        files.sort_by_cached_key(|file| {
+            let key = key(file);
+            (key.is_none(), Reverse(key), file.path.clone())
+        });
+    } else {
+        files.sort_by_cached_key(|file| {
+            let key = key(file);
+            (key.is_none(), key, file.path.clone())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn listed(path: &str, size_bytes: u64, modified_ms: Option<i64>) -> ListedFile {
+        ListedFile {
+            path: PathBuf::from(path),
+            size_bytes,
+            modified_ms,
+        }
+    }
+
+    fn names(files: &[ListedFile]) -> Vec<String> {
+        files.iter().map(ListedFile::name).collect()
+    }
+
+    #[test]
+    fn sorts_default_to_largest_and_oldest_first() {
+        let now = to_unix_ms(SystemTime::now()).unwrap();
+        let mut files = vec![
+            listed("c/small.log", 10, Some(now)),
+            listed("a/big.tmp", 900, None),
+            listed("b/mid.LOG", 300, Some(now - 5 * 86_400_000)),
+        ];
+        let by = |sort| ListQuery {
+            sort: Some(sort),
+            ..ListQuery::default()
+        };
+
+        by(ListSort::Size).apply(&mut files);
+        assert_eq!(names(&files), ["big.tmp", "mid.log", "small.log"]);
+        by(ListSort::Modified).apply(&mut files);
+        assert_eq!(names(&files), ["mid.log", "small.log", "big.tmp"]);
+        ListQuery {
+            order: Some(SortOrder::Desc),
+            ..by(ListSort::Name)
+        }
+        .apply(&mut files);
+        assert_eq!(names(&files), ["small.log", "mid.log", "big.tmp"]);
+    }
+
+    #[test]
+    fn filters_combine_and_huge_day_counts_do_not_overflow() {
+        let now = to_unix_ms(SystemTime::now()).unwrap();
+        let files = || {
+            vec![
+                listed("x/app.LOG", 500, Some(now - 40 * 86_400_000)),
+                listed("x/app-old.log", 50, Some(now - 40 * 86_400_000)),
+                listed("x/fresh.log", 800, Some(now)),
+                listed("x/setup.exe", 900, Some(now - 40 * 86_400_000)),
+            ]
+        };
+
+        let mut filtered = files();
+        ListQuery {
+            min_size_bytes: Some(100),
+            older_than_days: Some(30),
+            extensions: Some(vec![" .Log".to_string()]),
+            name_contains: Some("APP".to_string()),
+            ..ListQuery::default()
+        }
+        .apply(&mut filtered);
+        assert_eq!(names(&filtered), ["app.log"]);
+
+        let mut ancient = files();
+        ListQuery {
+            older_than_days: Some(u64::MAX),
+            ..ListQuery::default()
+        }
+        .apply(&mut ancient);
+        assert!(ancient.is_empty());
+
+        let mut recent = files();
+        ListQuery {
+            newer_than_days: Some(u64::MAX),
+            ..ListQuery::default()
+        }
+        .apply(&mut recent);
+        assert_eq!(recent.len(), 4);
+    }
+
+    #[test]
+    fn cursors_page_across_roots_without_walking_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots: Vec<PathBuf> = ["a", "b"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for (index, root) in roots.iter().enumerate() {
+            fs::create_dir(root).unwrap();
+            for file in 0..3 {
+                fs::write(root.join(format!("{}.tmp", file)), vec![0; index + 1]).unwrap();
+            }
+        }
+        let listings = Listings::default();
+        let walks = Cell::new(0);
+        let categories = || {
+            walks.set(walks.get() + 1);
+            Ok(vec![CategoryDef::for_roots("temp", roots.clone())])
+        };
+        let query = ListQuery::default();
+
+        let mut paths = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page =
+                list_category_items_sync(&listings, "temp", 4, cursor, &query, categories).unwrap();
+            assert_eq!(page.total_count, 6);
+            paths.extend(page.items.into_iter().map(|item| item.path));
+            cursor = page.next_cursor;
+            if !page.has_more {
+                break;
+            }
+        }
+        assert_eq!(walks.get(), 1);
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), 6);
+    }
+
+    #[test]
+    fn cursors_from_a_replaced_listing_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in 0..3 {
+            fs::write(dir.path().join(format!("{}.log", file)), [0; 10]).unwrap();
+        }
+        let listings = Listings::default();
+        let categories = || {
+            Ok(vec![CategoryDef::for_roots(
+                "logs",
+                vec![dir.path().to_path_buf()],
+            )])
+        };
+        let query = ListQuery::default();
+        let list = |cursor: Option<&str>| {
+            list_category_items_sync(
+                &listings,
+                "logs",
+                1,
+                cursor.map(str::to_string),
+                &query,
+                categories,
+            )
+        };
+
+        let first = list(None).unwrap();
+        let stale = first.next_cursor.unwrap();
+        assert!(list(Some(&stale)).is_ok());
+        list(None).unwrap();
+        assert_eq!(
+            list(Some(&stale)).err().as_deref(),
+            Some("Listing has expired. Reload the category.")
+        );
+        assert_eq!(
+            list(Some("not-a-cursor")).err().as_deref(),
+            Some("Invalid cursor.")
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{ProgressEvent, ProgressItem};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Instant,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+const PROGRESS_EVENT: &str = "operation-progress";
+const PROGRESS_INTERVAL_MS: u64 = 200;
+const PROGRESS_TOP_ITEMS: usize = 5;
+
+#[derive(Default)]
+pub(crate) struct Operations(Mutex<HashMap<String, CancelToken>>);
+
+impl Operations {
+    fn register(&self, id: &str) -> CancelToken {
+        let token = CancelToken::default();
+        self.lock().insert(id.to_string(), token.clone());
+        token
+    }
+
+    fn unregister(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    pub(crate) fn cancel(&self, id: &str) -> bool {
+        match self.lock().get(id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CancelToken>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[derive(Clone, Default)]
+struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+pub(crate) struct Operation {
+    id: Option<String>,
+    cancel: CancelToken,
+    pub(crate) progress: Progress,
+}
+
+impl Operation {
+    pub(crate) fn start(app: AppHandle, kind: &'static str, id: Option<String>) -> Self {
+        let cancel = match id.as_deref() {
+            Some(id) => app.state::<Operations>().register(id),
+            None => CancelToken::default(),
+        };
+        Self {
+            progress: Progress::new(Some(app), kind, id.clone()),
+            id,
+            cancel,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn detached(kind: &'static str) -> Self {
+        Self {
+            id: None,
+            cancel: CancelToken::default(),
+            progress: Progress::new(None, kind, None),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub(crate) fn app(&self) -> Option<&AppHandle> {
+        self.progress.app.as_ref()
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub(crate) fn finish(&self) {
+        self.progress.finish();
+        if let (Some(app), Some(id)) = (self.app(), self.id.as_deref()) {
+            app.state::<Operations>().unregister(id);
+        }
+    }
+}
+
+/// Throttled to one event per `PROGRESS_INTERVAL_MS` plus a final `done` event.
+pub(crate) struct Progress {
+    app: Option<AppHandle>,
+    pub(crate) operation: &'static str,
+    operation_id: Option<String>,
+    started: Instant,
+    files: AtomicU64,
+    bytes: AtomicU64,
+    last_emit_ms: AtomicU64,
+    largest_floor: AtomicU64,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    category_id: Option<String>,
+    current_root: Option<String>,
+    largest: Vec<ProgressItem>,
+}
+
+impl Progress {
+    fn new(app: Option<AppHandle>, operation: &'static str, operation_id: Option<String>) -> Self {
+        Self {
+            app,
+            operation,
+            operation_id,
+            started: Instant::now(),
+            files: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            last_emit_ms: AtomicU64::new(0),
+            largest_floor: AtomicU64::new(0),
+            state: Mutex::new(ProgressState::default()),
+        }
+    }
+
+    pub(crate) fn enter(&self, category_id: Option<&str>, root: &Path) {
+        {
+            let mut state = self.lock_state();
+            state.category_id = category_id.map(|value| value.to_string());
+            state.current_root = Some(root.to_string_lossy().to_string());
+        }
+        self.emit_throttled();
+    }
+
+    pub(crate) fn add_file(&self, path: &Path, size: u64) {
+        self.add_item(path, 1, size);
+    }
+
+    fn add_item(&self, path: &Path, count: u64, size: u64) {
+        if size > self.largest_floor.load(Ordering::Relaxed) {
+            let mut state = self.lock_state();
+            state.largest.push(ProgressItem {
+                path: path.to_string_lossy().to_string(),
+                size_bytes: size,
+            });
+            state.largest.sort_by_key(|item| Reverse(item.size_bytes));
+            state.largest.truncate(PROGRESS_TOP_ITEMS);
+            if state.largest.len() == PROGRESS_TOP_ITEMS {
+                let floor = state.largest.last().map_or(0, |item| item.size_bytes);
+                self.largest_floor.store(floor, Ordering::Relaxed);
+            }
+        }
+        self.add_count(count, size);
+    }
+
+    pub(crate) fn add_count(&self, count: u64, size: u64) {
+        self.files.fetch_add(count, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+        self.emit_throttled();
+    }
+
+    fn finish(&self) {
+        self.emit(true);
+    }
+
+    fn emit_throttled(&self) {
+        let now = self.started.elapsed().as_millis() as u64;
+        let last = self.last_emit_ms.load(Ordering::Relaxed);
+        if now < last.saturating_add(PROGRESS_INTERVAL_MS) {
+            return;
+        }
+        if self
+            .last_emit_ms
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+        self.emit(false);
+    }
+
+    fn emit(&self, done: bool) {
+        let Some(app) = &self.app else {
+            return;
+        };
+        let event = {
+            let state = self.lock_state();
+            ProgressEvent {
+                operation: self.operation,
+                operation_id: self.operation_id.clone(),
+                category_id: state.category_id.clone(),
+                current_root: state.current_root.clone(),
+                files: self.files.load(Ordering::Relaxed),
+                bytes: self.bytes.load(Ordering::Relaxed),
+                largest: state.largest.clone(),
+                done,
+            }
+        };
+        let _ = app.emit(PROGRESS_EVENT, event);
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, ProgressState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_counts_files_and_keeps_the_largest_in_order() {
+        let op = Operation::detached("test");
+        for (name, size) in [
+            ("a", 10),
+            ("b", 70),
+            ("c", 30),
+            ("d", 50),
+            ("e", 20),
+            ("f", 60),
+            ("g", 40),
+        ] {
+            op.progress.add_file(Path::new(name), size);
+        }
+        op.progress.add_count(3, 5);
+        op.finish();
+
+        let largest: Vec<u64> = op
+            .progress
+            .lock_state()
+            .largest
+            .iter()
+            .map(|item| item.size_bytes)
+            .collect();
+        assert_eq!(largest, [70, 60, 50, 40, 30]);
+        assert_eq!(op.progress.files.load(Ordering::Relaxed), 10);
+        assert_eq!(op.progress.bytes.load(Ordering::Relaxed), 285);
+    }
+
+    #[test]
+    fn cancel_reaches_only_the_registered_operation() {
+        let operations = Operations::default();
+        let first = operations.register("first");
+        let second = operations.register("second");
+
+        assert!(operations.cancel("first"));
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        operations.unregister("second");
+        assert!(!operations.cancel("second"));
+        assert!(!second.is_cancelled());
+    }
+}
@@ -0,0 +1,445 @@
+use crate::{
+    clean_recycle_bin_fast, cutoff_time, delete_file,
+    file_usage::reclaimed_size,
+    index::IndexSession,
+    is_within_roots,
+    listing::{list_category_files, ListedFile},
+    matches_cutoff, normalize_path_str,
+    operation::Operation,
+    scan::{scan_root, CategoryScan},
+    walk_failure, CategoryDef, CleanupError, CleanupResult,
+};
+use std::{cmp::Reverse, collections::HashSet, fs, io::ErrorKind, path::Path};
+use walkdir::WalkDir;
+
+pub(crate) trait CleanupProvider: Sync {
+    /// Used by the category-only scan; the full scan counts from its shared walk.
+    fn scan(
+        &self,
+        def: &CategoryDef,
+        root: &Path,
+        op: &Operation,
+        index: &IndexSession,
+    ) -> CategoryScan {
+        scan_root(root, cutoff_time(&def.kind), op, index)
+    }
+
+    fn list(&self, def: &CategoryDef) -> (Vec<ListedFile>, u64) {
+        list_category_files(def)
+    }
+
+    fn clean(
+        &self,
+        def: &CategoryDef,
+        excluded: &HashSet<String>,
+        op: &Operation,
+    ) -> CleanupResult {
+        if excluded.is_empty() {
+            if let Some(result) = self.fast_clear(def, op) {
+                return result;
+            }
+        }
+        clean_category_files(def, excluded, op)
+    }
+
+    fn clean_paths(&self, def: &CategoryDef, included: &[String], op: &Operation) -> CleanupResult {
+        clean_included_paths(def, included, op)
+    }
+
+    fn fast_clear(&self, _def: &CategoryDef, _op: &Operation) -> Option<CleanupResult> {
+        None
+    }
+}
+
+pub(crate) struct FileProvider;
+
+impl CleanupProvider for FileProvider {}
+
+pub(crate) struct CacheProvider;
+
+impl CleanupProvider for CacheProvider {
+    fn fast_clear(&self, def: &CategoryDef, op: &Operation) -> Option<CleanupResult> {
+        def.cleanup_dirs.then(|| clean_category_fast_dirs(def, op))
+    }
+}
+
+pub(crate) struct RecycleBinProvider;
+
+impl CleanupProvider for RecycleBinProvider {
+    fn fast_clear(&self, _def: &CategoryDef, op: &Operation) -> Option<CleanupResult> {
+        let result = clean_recycle_bin_fast();
+        op.progress
+            .add_count(result.deleted_count, result.deleted_bytes);
+        Some(result)
+    }
+}
+
+fn clean_category_files(
+    def: &CategoryDef,
+    excluded: &HashSet<String>,
+    op: &Operation,
+) -> CleanupResult {
+    let cutoff = cutoff_time(&def.kind);
+    let mut deleted_bytes = 0;
+    let mut deleted_count = 0;
+    let mut failed = Vec::new();
+    let mut dirs = Vec::new();
+
+    for root in &def.roots {
+        if op.is_cancelled() {
+            break;
+        }
+        if !root.exists() {
+            continue;
+        }
+        op.progress.enter(Some(&def.id), root);
+        if root.is_file() {
+            delete_file(
+                root,
+                cutoff,
+                excluded,
+                &mut deleted_bytes,
+                &mut deleted_count,
+                &mut failed,
+                op,
+            );
+            continue;
+        }
+
+        for entry in WalkDir::new(root).follow_links(false) {
+            if op.is_cancelled() {
+                break;
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    failed.push(walk_failure(&err, root));
+                    continue;
+                }
+            };
+            if entry.file_type().is_dir() {
+                if def.cleanup_dirs {
+                    dirs.push(entry.path().to_path_buf());
+                }
+                continue;
+            }
+            if entry.file_type().is_file() {
+                delete_file(
+                    entry.path(),
+                    cutoff,
+                    excluded,
+                    &mut deleted_bytes,
+                    &mut deleted_count,
+                    &mut failed,
+                    op,
+                );
+            }
+        }
+
+        if def.cleanup_dirs {
+            dirs.push(root.to_path_buf());
+        }
+    }
+
+    if def.cleanup_dirs && !dirs.is_empty() && !op.is_cancelled() {
+        dirs.sort_by_key(|dir| Reverse(dir.components().count()));
+        for dir in dirs {
+            if let Err(err) = fs::remove_dir(&dir) {
+                if !matches!(
+                    err.kind(),
+                    ErrorKind::NotFound | ErrorKind::DirectoryNotEmpty
+                ) {
+                    failed.push(CleanupError {
+                        path: dir.to_string_lossy().to_string(),
+                        message: err.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    CleanupResult {
+        deleted_bytes,
+        deleted_count,
+        failed,
+        cancelled: op.is_cancelled(),
+    }
+}
+
+fn clean_included_paths(def: &CategoryDef, included: &[String], op: &Operation) -> CleanupResult {
+    let cutoff = cutoff_time(&def.kind);
+    let mut deleted_bytes = 0;
+    let mut deleted_count = 0;
+    let mut failed = Vec::new();
+    let mut seen = HashSet::new();
+
+    for path_str in included {
+        if op.is_cancelled() {
+            break;
+        }
+        let normalized = normalize_path_str(path_str);
+        if !seen.insert(normalized) {
+            continue;
+        }
+        let path = Path::new(path_str);
+        if !is_within_roots(def, path) {
+            failed.push(CleanupError {
+                path: path_str.clone(),
+                message: "Path is outside cleanup scope.".to_string(),
+            });
+            continue;
+        }
+        let metadata = match path.metadata() {
+            Ok(meta) => meta,
+            Err(err) => {
+                failed.push(CleanupError {
+                    path: path_str.clone(),
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        if metadata.is_dir() {
+            failed.push(CleanupError {
+                path: path_str.clone(),
+                message: "Path is a directory.".to_string(),
+            });
+            continue;
+        }
+        if !matches_cutoff(&metadata, cutoff) {
+            continue;
+        }
+        let size = reclaimed_size(path, &metadata);
+        if let Err(err) = fs::remove_file(path) {
+            failed.push(CleanupError {
+                path: path_str.clone(),
+                message: err.to_string(),
+            });
+            continue;
+        }
+        deleted_bytes += size;
+        deleted_count += 1;
+        op.progress.add_file(path, size);
+    }
+
+    CleanupResult {
+        deleted_bytes,
+        deleted_count,
+        failed,
+        cancelled: op.is_cancelled(),
+    }
+}
+
+fn clean_category_fast_dirs(def: &CategoryDef, op: &Operation) -> CleanupResult {
+    let mut deleted_bytes: u64 = 0;
+    let mut deleted_count: u64 = 0;
+    let mut failed = Vec::new();
+
+    for root in &def.roots {
+        if op.is_cancelled() {
+            break;
+        }
+        if !root.exists() {
+            continue;
+        }
+        op.progress.enter(Some(&def.id), root);
+        let result = remove_tree(root, op);
+        deleted_bytes = deleted_bytes.saturating_add(result.deleted_bytes);
+        deleted_count = deleted_count.saturating_add(result.deleted_count);
+        failed.extend(result.failed);
+    }
+
+    CleanupResult {
+        deleted_bytes,
+        deleted_count,
+        failed,
+        cancelled: op.is_cancelled(),
+    }
+}
+
+pub(crate) fn remove_tree(root: &Path, op: &Operation) -> CleanupResult {
+    let mut deleted_bytes: u64 = 0;
+    let mut deleted_count: u64 = 0;
+    let mut failed = Vec::new();
+
+    if root.is_file() {
+        let size = root
+            .metadata()
+            .map(|meta| reclaimed_size(root, &meta))
+            .unwrap_or(0);
+        match fs::remove_file(root) {
+            Ok(()) => {
+                deleted_bytes = size;
+                deleted_count = 1;
+                op.progress.add_file(root, size);
+            }
+            Err(err) => failed.push(CleanupError {
+                path: root.to_string_lossy().to_string(),
+                message: err.to_string(),
+            }),
+        }
+    } else {
+        for entry in WalkDir::new(root).follow_links(false).contents_first(true) {
+            if op.is_cancelled() {
+                break;
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    failed.push(walk_failure(&err, root));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            if entry.file_type().is_dir() {
+                if let Err(err) = fs::remove_dir(path) {
+                    failed.push(CleanupError {
+                        path: path.to_string_lossy().to_string(),
+                        message: err.to_string(),
+                    });
+                }
+                continue;
+            }
+            let size = entry
+                .metadata()
+                .map(|meta| reclaimed_size(path, &meta))
+                .unwrap_or(0);
+            if let Err(err) = fs::remove_file(path) {
+                failed.push(CleanupError {
+                    path: path.to_string_lossy().to_string(),
+                    message: err.to_string(),
+                });
+                continue;
+            }
+            deleted_bytes = deleted_bytes.saturating_add(size);
+            deleted_count = deleted_count.saturating_add(1);
+            op.progress.add_file(path, size);
+        }
+    }
+
+    CleanupResult {
+        deleted_bytes,
+        deleted_count,
+        failed,
+        cancelled: op.is_cancelled(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clean_categories_sync, normalize_path, scan::scan_cleanup_items_sync, CleanRequest,
+    };
+    use std::collections::HashMap;
+
+    /// Reports fixed totals and clears by reporting a marker count.
+    struct FixedProvider;
+
+    static FIXED: FixedProvider = FixedProvider;
+
+    impl CleanupProvider for FixedProvider {
+        fn scan(
+            &self,
+            _def: &CategoryDef,
+            _root: &Path,
+            _op: &Operation,
+            _index: &IndexSession,
+        ) -> CategoryScan {
+            let mut scan = CategoryScan::default();
+            scan.size_bytes = 1234;
+            scan.file_count = 7;
+            scan
+        }
+
+        fn fast_clear(&self, _def: &CategoryDef, _op: &Operation) -> Option<CleanupResult> {
+            Some(CleanupResult {
+                deleted_bytes: 0,
+                deleted_count: 99,
+                failed: Vec::new(),
+                cancelled: false,
+            })
+        }
+    }
+
+    #[test]
+    fn cancelled_clean_leaves_files_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.tmp"), [0; 10]).unwrap();
+        let def = CategoryDef::for_roots("temp", vec![dir.path().to_path_buf()]);
+        let op = Operation::detached("test");
+        op.cancel();
+
+        let result = FileProvider.clean(&def, &HashSet::new(), &op);
+        assert!(result.cancelled);
+        assert_eq!(result.deleted_count, 0);
+        assert!(dir.path().join("a.tmp").exists());
+    }
+
+    #[test]
+    fn clean_removes_emptied_dirs_without_reporting_kept_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("temp");
+        fs::create_dir_all(root.join("emptied")).unwrap();
+        fs::create_dir_all(root.join("kept")).unwrap();
+        fs::write(root.join("emptied").join("a.tmp"), [0; 10]).unwrap();
+        let kept = root.join("kept").join("b.tmp");
+        fs::write(&kept, [0; 20]).unwrap();
+        let def = CategoryDef::for_roots("temp", vec![root.clone()]);
+        let excluded = HashSet::from([normalize_path(&kept)]);
+
+        let result = FileProvider.clean(&def, &excluded, &Operation::detached("test"));
+        assert_eq!((result.deleted_count, result.deleted_bytes), (1, 10));
+        assert!(result.failed.is_empty());
+        assert!(!root.join("emptied").exists());
+        assert!(kept.exists());
+    }
+
+    #[test]
+    fn scans_and_cleans_go_through_each_category_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("custom");
+        fs::create_dir(&root).unwrap();
+        let kept = root.join("kept.tmp");
+        fs::write(root.join("a.tmp"), [0; 10]).unwrap();
+        fs::write(&kept, [0; 20]).unwrap();
+        let categories = vec![CategoryDef {
+            provider: &FIXED,
+            ..CategoryDef::for_roots("custom", vec![root.clone()])
+        }];
+        let op = Operation::detached("test");
+
+        let scanned = scan_cleanup_items_sync(&categories, 1, false, &op).unwrap();
+        assert_eq!((scanned[0].size_bytes, scanned[0].file_count), (1234, 7));
+
+        let request = |excluded: Vec<String>| CleanRequest {
+            ids: vec!["custom".to_string()],
+            excluded_paths: HashMap::from([("custom".to_string(), excluded)]),
+            included_paths: HashMap::new(),
+            options: HashMap::new(),
+        };
+        let fast = clean_categories_sync(&categories, request(Vec::new()), &op).unwrap();
+        assert_eq!(fast.deleted_count, 99);
+        assert!(root.join("a.tmp").exists());
+
+        let excluded = vec![kept.to_string_lossy().to_string()];
+        let slow = clean_categories_sync(&categories, request(excluded), &op).unwrap();
+        assert_eq!((slow.deleted_count, slow.deleted_bytes), (1, 10));
+        assert!(kept.exists() && !root.join("a.tmp").exists());
+    }
+
+    #[test]
+    fn cache_provider_clears_whole_trees_only_without_exclusions() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("cache");
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested").join("a.bin"), [0; 10]).unwrap();
+        let def = CategoryDef::for_roots("cache", vec![root.clone()]);
+        let op = Operation::detached("test");
+
+        assert!(FileProvider.fast_clear(&def, &op).is_none());
+        let result = CacheProvider.clean(&def, &HashSet::new(), &op);
+        assert_eq!((result.deleted_count, result.deleted_bytes), (1, 10));
+        assert!(!root.join("nested").exists());
+    }
+}
@@ -0,0 +1,1405 @@
+use crate::{
+    cutoff::Cutoff,
+    cutoff_time, dedup_paths,
+    file_usage::{cluster_size, FileId, FileUsage},
+    heuristics::Heuristics,
+    index::{with_scan_index, DirRecord, IndexSession, IndexedLink, OpenDir},
+    is_within_root,
+    large_items::{LargeFiles, SuspiciousDirs, TopItems},
+    matches_cutoff, normalize_path, normalize_path_str,
+    operation::Operation,
+    snapshots::{
+        category_entries, large_item_entries, save_snapshot, SnapshotEntry, SnapshotKind,
+        SNAPSHOT_DIR_DEPTH,
+    },
+    to_unix_ms, BreakdownEntry, CategoryBreakdown, CategoryDef, CleanupCategory, FullScan,
+    LargeItem, LargeScan, RootScanErrors, ScanError, ScanErrorKind, CANCELLED_MESSAGE,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+const MAX_SCAN_ERROR_SAMPLES: usize = 20;
+const MAX_BREAKDOWN_EXTENSIONS: usize = 20;
+
+/// Breakdown age buckets and the age in hours each ends at; the last takes the rest.
+const AGE_BUCKETS: [(&str, i64); 4] = [
+    ("day", 24),
+    ("week", 24 * 7),
+    ("month", 24 * 30),
+    ("older", i64::MAX),
+];
+
+const UNKNOWN_HOUR: i64 = i64::MIN;
+
+pub(crate) fn scan_cleanup_items_sync(
+    categories: &[CategoryDef],
+    threads: usize,
+    force_rescan: bool,
+    op: &Operation,
+) -> Result<Vec<CleanupCategory>, String> {
+    let roots: Vec<PathBuf> = categories
+        .iter()
+        .flat_map(|def| def.roots.iter().cloned())
+        .collect();
+    let scans = with_scan_index(op, force_rescan, &roots, |index| {
+        scan_categories(categories, threads, op, index)
+    });
+    if op.is_cancelled() {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+    let result = to_cleanup_categories(categories, scans);
+    save_snapshot(op, None, category_entries(&result));
+    Ok(result)
+}
+
+fn to_cleanup_categories(
+    categories: &[CategoryDef],
+    scans: Vec<CategoryScan>,
+) -> Vec<CleanupCategory> {
+    categories
+        .iter()
+        .zip(scans)
+        .map(|(def, scan)| CleanupCategory {
+            id: def.id.clone(),
+            title: def.title.clone(),
+            description: def.description.clone(),
+            size_bytes: scan.size_bytes,
+            allocated_bytes: scan.allocated_bytes,
+            reclaimable_bytes: scan.reclaimable_bytes(),
+            file_count: scan.file_count,
+            inaccessible_count: scan.errors.iter().map(|root| root.count).sum(),
+            breakdown: scan.breakdown(),
+            retention_days: def.retention_days,
+            errors: scan.errors,
+        })
+        .collect()
+}
+
+pub(crate) fn scan_large_items_sync(
+    root: &Path,
+    categories: &[CategoryDef],
+    heuristics: &Heuristics,
+    (limit, min_size_bytes): (usize, u64),
+    threads: usize,
+    force_rescan: bool,
+    op: &Operation,
+) -> Result<LargeScan, String> {
+    let tasks = drive_walk_tasks(root);
+    let roots: Vec<PathBuf> = tasks.iter().map(|task| task.path.clone()).collect();
+    let mut consumers = with_scan_index(op, force_rescan, &roots, |index| {
+        walk_tasks(&tasks, threads, op, index, |_| ScanConsumers {
+            categories: None,
+            large_files: Some(LargeFiles::new(
+                categories,
+                heuristics,
+                min_size_bytes,
+                limit,
+            )),
+            suspicious_dirs: Some(SuspiciousDirs::new(heuristics, min_size_bytes, limit)),
+            dir_sizes: Some(DirSizes::new(root)),
+            drive_errors: Some(RootScanErrors::new(root.to_string_lossy().to_string())),
+        })
+    });
+    if op.is_cancelled() {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+    let mut entries = consumers.take_dir_entries();
+    let errors = consumers.take_drive_errors();
+    let items = consumers.into_large_items(limit);
+    entries.extend(large_item_entries(&items));
+    save_snapshot(op, Some(root), entries);
+    Ok(LargeScan { items, errors })
+}
+
+pub(crate) fn scan_full_sync(
+    root: &Path,
+    categories: &[CategoryDef],
+    heuristics: &Heuristics,
+    (limit, min_size_bytes): (usize, u64),
+    threads: usize,
+    force_rescan: bool,
+    op: &Operation,
+) -> Result<FullScan, String> {
+    let scopes: Vec<CategoryScope> = categories.iter().map(CategoryScope::new).collect();
+
+    let mut tasks = drive_walk_tasks(root);
+    let outside_roots = categories
+        .iter()
+        .flat_map(|def| def.roots.iter())
+        .filter(|path| !is_within_root(root, path))
+        .cloned()
+        .collect();
+    tasks.extend(dedup_paths(outside_roots).into_iter().map(|path| WalkTask {
+        path,
+        max_depth: usize::MAX,
+        drive_contents: false,
+    }));
+
+    let roots: Vec<PathBuf> = tasks.iter().map(|task| task.path.clone()).collect();
+    let mut consumers = with_scan_index(op, force_rescan, &roots, |index| {
+        walk_tasks(&tasks, threads, op, index, |task| ScanConsumers {
+            categories: Some(CategoryTotals::new(&scopes)),
+            large_files: task
+                .drive_contents
+                .then(|| LargeFiles::new(categories, heuristics, min_size_bytes, limit)),
+            suspicious_dirs: task
+                .drive_contents
+                .then(|| SuspiciousDirs::new(heuristics, min_size_bytes, limit)),
+            dir_sizes: task.drive_contents.then(|| DirSizes::new(root)),
+            drive_errors: task
+                .drive_contents
+                .then(|| RootScanErrors::new(root.to_string_lossy().to_string())),
+        })
+    });
+    if op.is_cancelled() {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+
+    let scans = consumers
+        .categories
+        .as_ref()
+        .map(|totals| totals.scans.clone())
+        .unwrap_or_else(|| vec![CategoryScan::default(); categories.len()]);
+    let mut entries = consumers.take_dir_entries();
+    let large_item_errors = consumers.take_drive_errors();
+    let result = FullScan {
+        categories: to_cleanup_categories(categories, scans),
+        large_items: consumers.into_large_items(limit),
+        large_item_errors,
+    };
+    entries.extend(category_entries(&result.categories));
+    entries.extend(large_item_entries(&result.large_items));
+    save_snapshot(op, Some(root), entries);
+    Ok(result)
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct CategoryScan {
+    pub(crate) size_bytes: u64,
+    allocated_bytes: u64,
+    unlinked_bytes: u64,
+    pub(crate) file_count: u64,
+    linked: HashMap<FileId, LinkedFile>,
+    errors: Vec<RootScanErrors>,
+    roots: HashMap<String, SizeCount>,
+    files: FileBreakdown,
+}
+
+/// Only reclaimable once the scan has seen every link.
+#[derive(Clone)]
+struct LinkedFile {
+    size_bytes: u64,
+    allocated_bytes: u64,
+    links: u32,
+    seen: u32,
+    root: String,
+    extension: String,
+    hour: i64,
+}
+
+impl CategoryScan {
+    fn add(&mut self, root: &Path, path: &Path, metadata: &fs::Metadata, usage: &FileUsage) {
+        let root = root.to_string_lossy();
+        let extension = extension_key(path);
+        let hour = modified_hour(metadata);
+        self.file_count += 1;
+        match usage.linked {
+            Some(identity) => self.add_linked(
+                &root,
+                &IndexedLink {
+                    identity,
+                    size_bytes: usage.size_bytes,
+                    allocated_bytes: usage.allocated_bytes,
+                    extension,
+                    hour,
+                },
+            ),
+            None => {
+                self.size_bytes += usage.size_bytes;
+                self.allocated_bytes += usage.allocated_bytes;
+                self.unlinked_bytes += usage.size_bytes;
+                add_size_count(&mut self.roots, &root, usage.size_bytes, 1);
+                self.files.add(&extension, hour, usage.size_bytes, 1);
+            }
+        }
+    }
+
+    fn add_linked(&mut self, root: &str, link: &IndexedLink) {
+        let file = self.linked.entry(link.identity.id).or_insert(LinkedFile {
+            size_bytes: link.size_bytes,
+            allocated_bytes: link.allocated_bytes,
+            links: link.identity.links,
+            seen: 0,
+            root: root.to_string(),
+            extension: link.extension.clone(),
+            hour: link.hour,
+        });
+        let mut size = 0;
+        if file.seen == 0 {
+            size = link.size_bytes;
+            self.size_bytes += link.size_bytes;
+            self.allocated_bytes += link.allocated_bytes;
+        }
+        file.seen += 1;
+        add_size_count(&mut self.roots, root, size, 1);
+        self.files.add(&link.extension, link.hour, size, 1);
+    }
+
+    fn add_record(&mut self, root: &Path, record: &DirRecord) {
+        let root = root.to_string_lossy();
+        self.size_bytes += record.file_bytes;
+        self.allocated_bytes += record.allocated_bytes;
+        self.unlinked_bytes += record.file_bytes;
+        self.file_count += record.file_count;
+        let unlinked_count = record
+            .file_count
+            .saturating_sub(record.linked_files.len() as u64);
+        add_size_count(&mut self.roots, &root, record.file_bytes, unlinked_count);
+        self.files.merge(&record.files);
+        for file in &record.linked_files {
+            self.add_linked(&root, file);
+        }
+    }
+
+    fn add_error(&mut self, root: &Path, error: ScanError) {
+        let root = root.to_string_lossy();
+        let index = match self.errors.iter().position(|errors| errors.root == root) {
+            Some(index) => index,
+            None => {
+                self.errors.push(RootScanErrors::new(root.to_string()));
+                self.errors.len() - 1
+            }
+        };
+        self.errors[index].add(error);
+    }
+
+    fn merge(&mut self, other: CategoryScan) {
+        for other in other.errors {
+            match self
+                .errors
+                .iter_mut()
+                .find(|errors| errors.root == other.root)
+            {
+                Some(errors) => errors.merge(other),
+                None => self.errors.push(other),
+            }
+        }
+        self.size_bytes += other.size_bytes;
+        self.allocated_bytes += other.allocated_bytes;
+        self.unlinked_bytes += other.unlinked_bytes;
+        self.file_count += other.file_count;
+        for (root, totals) in &other.roots {
+            add_size_count(&mut self.roots, root, totals.size_bytes, totals.file_count);
+        }
+        self.files.merge(&other.files);
+        for (id, file) in other.linked {
+            match self.linked.entry(id) {
+                Entry::Occupied(mut entry) => {
+                    self.size_bytes = self.size_bytes.saturating_sub(file.size_bytes);
+                    self.allocated_bytes =
+                        self.allocated_bytes.saturating_sub(file.allocated_bytes);
+                    if let Some(totals) = self.roots.get_mut(&file.root) {
+                        totals.size_bytes = totals.size_bytes.saturating_sub(file.size_bytes);
+                    }
+                    self.files
+                        .remove_bytes(&file.extension, file.hour, file.size_bytes);
+                    entry.get_mut().seen += file.seen;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(file);
+                }
+            }
+        }
+    }
+
+    fn reclaimable_bytes(&self) -> u64 {
+        let linked: u64 = self
+            .linked
+            .values()
+            .filter(|file| file.seen >= file.links)
+            .map(|file| file.size_bytes)
+            .sum();
+        self.unlinked_bytes + linked
+    }
+
+    fn breakdown(&self) -> CategoryBreakdown {
+        let roots = sorted_entries(
+            self.roots
+                .iter()
+                .map(|(root, totals)| (root.clone(), *totals)),
+        );
+        let mut extensions = sorted_entries(
+            self.files
+                .extensions
+                .iter()
+                .map(|(extension, totals)| (extension.clone(), *totals)),
+        );
+        if extensions.len() > MAX_BREAKDOWN_EXTENSIONS {
+            let rest = extensions.split_off(MAX_BREAKDOWN_EXTENSIONS - 1);
+            extensions.push(BreakdownEntry {
+                key: "*".to_string(),
+                size_bytes: rest.iter().map(|entry| entry.size_bytes).sum(),
+                file_count: rest.iter().map(|entry| entry.file_count).sum(),
+            });
+        }
+        let now_hour = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| (elapsed.as_secs() / 3600) as i64)
+            .unwrap_or(0);
+        let mut ages: Vec<BreakdownEntry> = AGE_BUCKETS
+            .iter()
+            .map(|(key, _)| BreakdownEntry {
+                key: key.to_string(),
+                size_bytes: 0,
+                file_count: 0,
+            })
+            .collect();
+        for (hour, totals) in &self.files.hours {
+            let age = now_hour.saturating_sub(*hour);
+            let bucket = AGE_BUCKETS
+                .iter()
+                .position(|(_, max_hours)| age < *max_hours)
+                .unwrap_or(AGE_BUCKETS.len() - 1);
+            ages[bucket].size_bytes += totals.size_bytes;
+            ages[bucket].file_count += totals.file_count;
+        }
+        CategoryBreakdown {
+            roots,
+            extensions,
+            ages,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SizeCount {
+    size_bytes: u64,
+    file_count: u64,
+}
+
+fn add_size_count(map: &mut HashMap<String, SizeCount>, key: &str, size: u64, count: u64) {
+    match map.get_mut(key) {
+        Some(totals) => {
+            totals.size_bytes += size;
+            totals.file_count += count;
+        }
+        None => {
+            map.insert(
+                key.to_string(),
+                SizeCount {
+                    size_bytes: size,
+                    file_count: count,
+                },
+            );
+        }
+    }
+}
+
+/// Keyed by modified hour so age buckets are drawn when a result is read.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileBreakdown {
+    extensions: HashMap<String, SizeCount>,
+    hours: HashMap<i64, SizeCount>,
+}
+
+impl FileBreakdown {
+    pub(crate) fn add(&mut self, extension: &str, hour: i64, size: u64, count: u64) {
+        add_size_count(&mut self.extensions, extension, size, count);
+        let totals = self.hours.entry(hour).or_default();
+        totals.size_bytes += size;
+        totals.file_count += count;
+    }
+
+    fn merge(&mut self, other: &FileBreakdown) {
+        for (extension, totals) in &other.extensions {
+            add_size_count(
+                &mut self.extensions,
+                extension,
+                totals.size_bytes,
+                totals.file_count,
+            );
+        }
+        for (hour, totals) in &other.hours {
+            let entry = self.hours.entry(*hour).or_default();
+            entry.size_bytes += totals.size_bytes;
+            entry.file_count += totals.file_count;
+        }
+    }
+
+    fn remove_bytes(&mut self, extension: &str, hour: i64, size: u64) {
+        if let Some(totals) = self.extensions.get_mut(extension) {
+            totals.size_bytes = totals.size_bytes.saturating_sub(size);
+        }
+        if let Some(totals) = self.hours.get_mut(&hour) {
+            totals.size_bytes = totals.size_bytes.saturating_sub(size);
+        }
+    }
+}
+
+fn sorted_entries(totals: impl Iterator<Item = (String, SizeCount)>) -> Vec<BreakdownEntry> {
+    let mut entries: Vec<BreakdownEntry> = totals
+        .map(|(key, totals)| BreakdownEntry {
+            key,
+            size_bytes: totals.size_bytes,
+            file_count: totals.file_count,
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.size_bytes
+            .cmp(&a.size_bytes)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    entries
+}
+
+pub(crate) fn extension_key(path: &Path) -> String {
+    path.extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy().to_lowercase()))
+        .unwrap_or_default()
+}
+
+pub(crate) fn modified_hour(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(to_unix_ms)
+        .map(|ms| ms.div_euclid(3_600_000))
+        .unwrap_or(UNKNOWN_HOUR)
+}
+
+fn scan_categories(
+    categories: &[CategoryDef],
+    threads: usize,
+    op: &Operation,
+    index: &IndexSession,
+) -> Vec<CategoryScan> {
+    let tasks: Vec<(usize, &Path)> = categories
+        .iter()
+        .enumerate()
+        .flat_map(|(index, def)| def.roots.iter().map(move |root| (index, root.as_path())))
+        .collect();
+
+    let root_scans = run_parallel(&tasks, threads, |(position, root)| {
+        let def = &categories[*position];
+        op.progress.enter(Some(&def.id), root);
+        def.provider.scan(def, root, op, index)
+    });
+
+    let mut scans = vec![CategoryScan::default(); categories.len()];
+    for ((position, _), scan) in tasks.iter().zip(root_scans) {
+        scans[*position].merge(scan);
+    }
+    scans
+}
+
+/// Results come back in the order of `items`.
+pub(crate) fn run_parallel<T, R, F>(items: &[T], threads: usize, task: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = threads.clamp(1, items.len().max(1));
+    if threads == 1 {
+        return items.iter().map(task).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = task(item);
+                results
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(|err| err.into_inner());
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub(crate) fn scan_root(
+    root: &Path,
+    cutoff: Option<Cutoff>,
+    op: &Operation,
+    index: &IndexSession,
+) -> CategoryScan {
+    let mut totals = RootTotals {
+        root: root.to_path_buf(),
+        cutoff,
+        scan: CategoryScan::default(),
+    };
+    walk_root(root, usize::MAX, &mut totals, op, index);
+    totals.scan
+}
+
+pub(crate) trait ScanVisitor {
+    fn visit_file(&mut self, path: &Path, metadata: &fs::Metadata, usage: &FileUsage);
+
+    /// False lets an unchanged directory be replayed from its index record.
+    fn needs_files(&self, dir: &Path) -> bool;
+
+    fn visit_record(&mut self, dir: &Path, record: &DirRecord);
+
+    fn visit_error(&mut self, _error: &ScanError) {}
+}
+
+impl RootScanErrors {
+    fn new(root: String) -> Self {
+        Self {
+            root,
+            count: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, error: ScanError) {
+        self.count += 1;
+        if self.errors.len() < MAX_SCAN_ERROR_SAMPLES {
+            self.errors.push(error);
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.count += other.count;
+        let room = MAX_SCAN_ERROR_SAMPLES.saturating_sub(self.errors.len());
+        self.errors.extend(other.errors.into_iter().take(room));
+    }
+}
+
+impl ScanError {
+    fn new(path: &Path, err: &std::io::Error) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            kind: ScanErrorKind::from(err.kind()),
+            message: err.to_string(),
+        }
+    }
+
+    fn from_walk(err: &walkdir::Error, fallback: &Path) -> Self {
+        let path = err.path().unwrap_or(fallback);
+        Self {
+            path: path.to_string_lossy().to_string(),
+            kind: err
+                .io_error()
+                .map(|err| ScanErrorKind::from(err.kind()))
+                .unwrap_or(ScanErrorKind::Other),
+            message: err.to_string(),
+        }
+    }
+}
+
+struct WalkTask {
+    path: PathBuf,
+    max_depth: usize,
+    drive_contents: bool,
+}
+
+pub(crate) fn walk_root(
+    root: &Path,
+    max_depth: usize,
+    visitor: &mut dyn ScanVisitor,
+    op: &Operation,
+    index: &IndexSession,
+) {
+    walk_dir(root, max_depth, visitor, op, index, cluster_size(root));
+}
+
+fn walk_dir(
+    root: &Path,
+    max_depth: usize,
+    visitor: &mut dyn ScanVisitor,
+    op: &Operation,
+    index: &IndexSession,
+    cluster_bytes: u64,
+) {
+    let metadata = match fs::symlink_metadata(root) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return,
+        Err(err) => {
+            visitor.visit_error(&ScanError::new(root, &err));
+            return;
+        }
+    };
+    if metadata.is_file() {
+        let usage = FileUsage::of(root, &metadata, cluster_bytes);
+        visitor.visit_file(root, &metadata, &usage);
+        op.progress.add_file(root, metadata.len());
+        return;
+    }
+
+    let mut open: Vec<OpenDir> = Vec::new();
+    let mut walker = WalkDir::new(root)
+        .follow_links(false)
+        .max_depth(max_depth)
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                let error = ScanError::from_walk(&err, root);
+                mark_incomplete(&mut open, Path::new(&error.path));
+                visitor.visit_error(&error);
+                continue;
+            }
+        };
+        if op.is_cancelled() {
+            return;
+        }
+        let depth = entry.depth();
+        index.close_dirs(&mut open, depth);
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if entry.file_type().is_dir() {
+            if let Some(parent) = open.last_mut().filter(|dir| dir.depth + 1 == depth) {
+                parent.record.subdirs.push(name);
+            }
+            if depth >= max_depth {
+                continue;
+            }
+            let modified_ms = entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok())
+                .and_then(to_unix_ms);
+            let path = entry.path();
+            let key = normalize_path(path);
+            let reused = (!visitor.needs_files(path))
+                .then(|| index.reuse(path, &key, modified_ms))
+                .flatten();
+            if let Some(record) = reused {
+                walker.skip_current_dir();
+                visitor.visit_record(path, &record);
+                op.progress.add_count(record.file_count, record.file_bytes);
+                let subdirs = (depth + 1 < max_depth).then(|| record.subdirs.clone());
+                index.replayed(key, record);
+                for subdir in subdirs.into_iter().flatten() {
+                    walk_dir(
+                        &path.join(subdir),
+                        max_depth - depth - 1,
+                        visitor,
+                        op,
+                        index,
+                        cluster_bytes,
+                    );
+                }
+                continue;
+            }
+            open.push(OpenDir {
+                depth,
+                key,
+                record: DirRecord::new(modified_ms),
+                complete: true,
+            });
+            continue;
+        }
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        match entry.metadata() {
+            Ok(metadata) => {
+                let usage = FileUsage::of(entry.path(), &metadata, cluster_bytes);
+                visitor.visit_file(entry.path(), &metadata, &usage);
+                op.progress.add_file(entry.path(), metadata.len());
+                if let Some(parent) = open.last_mut().filter(|dir| dir.depth + 1 == depth) {
+                    parent
+                        .record
+                        .add_file(name, &metadata, &usage, index.recent_after_ms);
+                }
+            }
+            Err(err) => {
+                let error = ScanError::from_walk(&err, entry.path());
+                mark_incomplete(&mut open, entry.path());
+                visitor.visit_error(&error);
+            }
+        }
+    }
+    index.close_dirs(&mut open, 0);
+}
+
+fn mark_incomplete(open: &mut [OpenDir], path: &Path) {
+    let key = normalize_path(path);
+    let parent = path.parent().map(normalize_path);
+    if let Some(dir) = open
+        .iter_mut()
+        .rev()
+        .find(|dir| dir.key == key || Some(&dir.key) == parent.as_ref())
+    {
+        dir.complete = false;
+    }
+}
+
+fn drive_walk_tasks(root: &Path) -> Vec<WalkTask> {
+    let mut tasks = vec![WalkTask {
+        path: root.to_path_buf(),
+        max_depth: 1,
+        drive_contents: true,
+    }];
+    if let Ok(entries) = fs::read_dir(root) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let is_dir = entry
+                .file_type()
+                .map(|file_type| file_type.is_dir())
+                .unwrap_or(false);
+            if is_dir {
+                tasks.push(WalkTask {
+                    path: entry.path(),
+                    max_depth: usize::MAX,
+                    drive_contents: true,
+                });
+            }
+        }
+    }
+    tasks
+}
+
+fn walk_tasks<'a, F>(
+    tasks: &[WalkTask],
+    threads: usize,
+    op: &Operation,
+    index: &IndexSession,
+    make: F,
+) -> ScanConsumers<'a>
+where
+    F: Fn(&WalkTask) -> ScanConsumers<'a> + Sync,
+{
+    let results = run_parallel(tasks, threads, |task| {
+        let mut consumers = make(task);
+        op.progress.enter(None, &task.path);
+        walk_root(&task.path, task.max_depth, &mut consumers, op, index);
+        consumers
+    });
+    let mut results = results.into_iter();
+    let mut merged = results.next().unwrap_or_default();
+    for consumers in results {
+        merged.merge(consumers);
+    }
+    merged
+}
+
+struct RootTotals {
+    root: PathBuf,
+    cutoff: Option<Cutoff>,
+    scan: CategoryScan,
+}
+
+impl ScanVisitor for RootTotals {
+    fn visit_file(&mut self, path: &Path, metadata: &fs::Metadata, usage: &FileUsage) {
+        if matches_cutoff(metadata, self.cutoff) {
+            self.scan.add(&self.root, path, metadata, usage);
+        }
+    }
+
+    fn needs_files(&self, _dir: &Path) -> bool {
+        self.cutoff.is_some()
+    }
+
+    fn visit_record(&mut self, _dir: &Path, record: &DirRecord) {
+        self.scan.add_record(&self.root, record);
+    }
+
+    fn visit_error(&mut self, error: &ScanError) {
+        self.scan.add_error(&self.root, error.clone());
+    }
+}
+
+struct CategoryScope {
+    roots: Vec<ScopeRoot>,
+    paths: Vec<PathBuf>,
+    cutoff: Option<Cutoff>,
+}
+
+struct ScopeRoot {
+    key: String,
+    prefix: String,
+    is_file: bool,
+}
+
+impl CategoryScope {
+    fn new(def: &CategoryDef) -> Self {
+        let roots = def
+            .roots
+            .iter()
+            .map(|root| {
+                let key = normalize_path(root);
+                ScopeRoot {
+                    prefix: format!("{}\\", key.trim_end_matches('\\')),
+                    key,
+                    is_file: root.is_file(),
+                }
+            })
+            .collect();
+        Self {
+            roots,
+            paths: def.roots.clone(),
+            cutoff: cutoff_time(&def.kind),
+        }
+    }
+
+    fn root_affected_by(&self, target: &str) -> Option<&Path> {
+        let target_prefix = format!("{}\\", target.trim_end_matches('\\'));
+        self.roots
+            .iter()
+            .position(|root| {
+                target == root.key
+                    || target.starts_with(&root.prefix)
+                    || root.key.starts_with(&target_prefix)
+            })
+            .map(|index| self.paths[index].as_path())
+    }
+
+    fn contains(&self, target: &str) -> bool {
+        self.root_of(target).is_some()
+    }
+
+    fn root_of(&self, target: &str) -> Option<&Path> {
+        self.roots
+            .iter()
+            .position(|root| {
+                if root.is_file {
+                    return target == root.key;
+                }
+                target == root.key || target.starts_with(&root.prefix)
+            })
+            .map(|index| self.paths[index].as_path())
+    }
+
+    fn has_file_root_in(&self, dir: &str) -> bool {
+        let dir = dir.trim_end_matches('\\');
+        self.roots.iter().any(|root| {
+            root.is_file
+                && root
+                    .key
+                    .rsplit_once('\\')
+                    .is_some_and(|(parent, _)| parent.trim_end_matches('\\') == dir)
+        })
+    }
+}
+
+struct CategoryTotals<'a> {
+    scopes: &'a [CategoryScope],
+    scans: Vec<CategoryScan>,
+}
+
+impl<'a> CategoryTotals<'a> {
+    fn new(scopes: &'a [CategoryScope]) -> Self {
+        Self {
+            scopes,
+            scans: vec![CategoryScan::default(); scopes.len()],
+        }
+    }
+}
+
+impl ScanVisitor for CategoryTotals<'_> {
+    fn visit_file(&mut self, path: &Path, metadata: &fs::Metadata, usage: &FileUsage) {
+        let target = normalize_path(path);
+        for (scope, scan) in self.scopes.iter().zip(self.scans.iter_mut()) {
+            if !matches_cutoff(metadata, scope.cutoff) {
+                continue;
+            }
+            if let Some(root) = scope.root_of(&target) {
+                scan.add(root, path, metadata, usage);
+            }
+        }
+    }
+
+    fn needs_files(&self, dir: &Path) -> bool {
+        let target = normalize_path(dir);
+        self.scopes.iter().any(|scope| {
+            (scope.cutoff.is_some() && scope.contains(&target)) || scope.has_file_root_in(&target)
+        })
+    }
+
+    fn visit_record(&mut self, dir: &Path, record: &DirRecord) {
+        let target = normalize_path(dir);
+        for (scope, scan) in self.scopes.iter().zip(self.scans.iter_mut()) {
+            if let Some(root) = scope.root_of(&target) {
+                scan.add_record(root, record);
+            }
+        }
+    }
+
+    fn visit_error(&mut self, error: &ScanError) {
+        let target = normalize_path_str(&error.path);
+        for (scope, scan) in self.scopes.iter().zip(self.scans.iter_mut()) {
+            if let Some(root) = scope.root_affected_by(&target) {
+                scan.add_error(root, error.clone());
+            }
+        }
+    }
+}
+
+struct DirSizes {
+    base: PathBuf,
+    sizes: HashMap<PathBuf, u64>,
+}
+
+impl DirSizes {
+    fn new(base: &Path) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            sizes: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, dir: &Path, size: u64) {
+        let Ok(relative) = dir.strip_prefix(&self.base) else {
+            return;
+        };
+        let mut ancestor = self.base.clone();
+        for component in relative.components().take(SNAPSHOT_DIR_DEPTH) {
+            ancestor.push(component);
+            match self.sizes.get_mut(&ancestor) {
+                Some(total) => *total += size,
+                None => {
+                    self.sizes.insert(ancestor.clone(), size);
+                }
+            }
+        }
+    }
+}
+
+impl ScanVisitor for DirSizes {
+    fn visit_file(&mut self, path: &Path, _metadata: &fs::Metadata, usage: &FileUsage) {
+        if let Some(dir) = path.parent() {
+            self.add(dir, usage.size_bytes);
+        }
+    }
+
+    fn needs_files(&self, _dir: &Path) -> bool {
+        false
+    }
+
+    fn visit_record(&mut self, dir: &Path, record: &DirRecord) {
+        let linked: u64 = record.linked_files.iter().map(|file| file.size_bytes).sum();
+        self.add(dir, record.file_bytes + linked);
+    }
+}
+
+#[derive(Default)]
+struct ScanConsumers<'a> {
+    categories: Option<CategoryTotals<'a>>,
+    large_files: Option<LargeFiles<'a>>,
+    suspicious_dirs: Option<SuspiciousDirs<'a>>,
+    dir_sizes: Option<DirSizes>,
+    drive_errors: Option<RootScanErrors>,
+}
+
+impl ScanConsumers<'_> {
+    fn merge(&mut self, other: Self) {
+        match (&mut self.categories, other.categories) {
+            (Some(totals), Some(other)) => {
+                for (scan, other) in totals.scans.iter_mut().zip(other.scans) {
+                    scan.merge(other);
+                }
+            }
+            (slot @ None, other) => *slot = other,
+            _ => {}
+        }
+        match (&mut self.large_files, other.large_files) {
+            (Some(files), Some(other)) => files.top.merge(other.top),
+            (slot @ None, other) => *slot = other,
+            _ => {}
+        }
+        match (&mut self.suspicious_dirs, other.suspicious_dirs) {
+            (Some(dirs), Some(mut other)) => {
+                dirs.finish();
+                other.finish();
+                dirs.top.merge(other.top);
+            }
+            (slot @ None, other) => *slot = other,
+            _ => {}
+        }
+        match (&mut self.dir_sizes, other.dir_sizes) {
+            (Some(sizes), Some(other)) => {
+                for (path, size) in other.sizes {
+                    *sizes.sizes.entry(path).or_default() += size;
+                }
+            }
+            (slot @ None, other) => *slot = other,
+            _ => {}
+        }
+        match (&mut self.drive_errors, other.drive_errors) {
+            (Some(errors), Some(other)) => errors.merge(other),
+            (slot @ None, other) => *slot = other,
+            _ => {}
+        }
+    }
+
+    fn take_dir_entries(&mut self) -> Vec<SnapshotEntry> {
+        let Some(sizes) = self.dir_sizes.take() else {
+            return Vec::new();
+        };
+        sizes
+            .sizes
+            .into_iter()
+            .map(|(path, size)| SnapshotEntry {
+                kind: SnapshotKind::Folder,
+                path: path.to_string_lossy().to_string(),
+                size_bytes: size,
+            })
+            .collect()
+    }
+
+    fn take_drive_errors(&mut self) -> Vec<RootScanErrors> {
+        self.drive_errors
+            .take()
+            .filter(|errors| errors.count > 0)
+            .into_iter()
+            .collect()
+    }
+
+    fn into_large_items(self, limit: usize) -> Vec<LargeItem> {
+        let mut top = TopItems::new(limit);
+        if let Some(files) = self.large_files {
+            top.merge(files.top);
+        }
+        if let Some(mut dirs) = self.suspicious_dirs {
+            dirs.finish();
+            top.merge(dirs.top);
+        }
+        let mut items = top.into_sorted();
+        for item in &mut items {
+            item.read_details();
+        }
+        items
+    }
+}
+
+impl ScanVisitor for ScanConsumers<'_> {
+    fn visit_file(&mut self, path: &Path, metadata: &fs::Metadata, usage: &FileUsage) {
+        if let Some(totals) = self.categories.as_mut() {
+            totals.visit_file(path, metadata, usage);
+        }
+        if let Some(files) = self.large_files.as_mut() {
+            files.visit_file(path, metadata, usage);
+        }
+        if let Some(dirs) = self.suspicious_dirs.as_mut() {
+            dirs.visit_file(path, metadata, usage);
+        }
+        if let Some(sizes) = self.dir_sizes.as_mut() {
+            sizes.visit_file(path, metadata, usage);
+        }
+    }
+
+    fn needs_files(&self, dir: &Path) -> bool {
+        self.categories
+            .as_ref()
+            .is_some_and(|totals| totals.needs_files(dir))
+            || self
+                .large_files
+                .as_ref()
+                .is_some_and(|files| files.needs_files(dir))
+            || self
+                .suspicious_dirs
+                .as_ref()
+                .is_some_and(|dirs| dirs.needs_files(dir))
+    }
+
+    fn visit_record(&mut self, dir: &Path, record: &DirRecord) {
+        if let Some(totals) = self.categories.as_mut() {
+            totals.visit_record(dir, record);
+        }
+        if let Some(files) = self.large_files.as_mut() {
+            files.visit_record(dir, record);
+        }
+        if let Some(dirs) = self.suspicious_dirs.as_mut() {
+            dirs.visit_record(dir, record);
+        }
+        if let Some(sizes) = self.dir_sizes.as_mut() {
+            sizes.visit_record(dir, record);
+        }
+    }
+
+    fn visit_error(&mut self, error: &ScanError) {
+        if let Some(totals) = self.categories.as_mut() {
+            totals.visit_error(error);
+        }
+        if let Some(errors) = self.drive_errors.as_mut() {
+            errors.add(error.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_category_scan_keeps_category_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let categories: Vec<CategoryDef> = (0..5)
+            .map(|index| {
+                let root = dir.path().join(format!("root{}", index));
+                fs::create_dir_all(root.join("nested")).unwrap();
+                fs::write(root.join("a.tmp"), vec![0; 100 * (index + 1)]).unwrap();
+                fs::write(root.join("nested").join("b.log"), [0; 10]).unwrap();
+                CategoryDef::for_roots(&format!("cat{}", index), vec![root])
+            })
+            .collect();
+
+        for threads in [1, 3, 8] {
+            let op = Operation::detached("test");
+            let result = scan_cleanup_items_sync(&categories, threads, false, &op).unwrap();
+            let totals: Vec<(&str, u64, u64)> = result
+                .iter()
+                .map(|category| {
+                    (
+                        category.id.as_str(),
+                        category.size_bytes,
+                        category.file_count,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                totals,
+                [
+                    ("cat0", 110, 2),
+                    ("cat1", 210, 2),
+                    ("cat2", 310, 2),
+                    ("cat3", 410, 2),
+                    ("cat4", 510, 2),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn full_scan_shares_one_walk_for_categories_and_large_items() {
+        let volume = tempfile::tempdir().unwrap();
+        let cache = volume.path().join("cache");
+        fs::create_dir_all(cache.join("nested")).unwrap();
+        fs::write(cache.join("a.tmp"), [0; 300]).unwrap();
+        fs::write(cache.join("nested").join("big.bin"), vec![0; 5000]).unwrap();
+        fs::create_dir(volume.path().join("videos")).unwrap();
+        fs::write(
+            volume.path().join("videos").join("movie.mp4"),
+            vec![0; 8000],
+        )
+        .unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("c.log"), [0; 40]).unwrap();
+        let categories = vec![
+            CategoryDef::for_roots("cache", vec![cache.clone()]),
+            CategoryDef::for_roots("outside", vec![outside.path().to_path_buf()]),
+        ];
+
+        let op = Operation::detached("test");
+        let heuristics = Heuristics::builtin();
+        let full = scan_full_sync(
+            volume.path(),
+            &categories,
+            &heuristics,
+            (10, 1000),
+            2,
+            false,
+            &op,
+        )
+        .unwrap();
+        let quick = scan_cleanup_items_sync(&categories, 2, false, &op).unwrap();
+
+        let totals = |categories: &[CleanupCategory]| -> Vec<(String, u64, u64)> {
+            categories
+                .iter()
+                .map(|category| {
+                    (
+                        category.id.clone(),
+                        category.size_bytes,
+                        category.file_count,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(totals(&full.categories), totals(&quick));
+        assert_eq!(
+            totals(&full.categories),
+            [
+                ("cache".to_string(), 5300, 2),
+                ("outside".to_string(), 40, 1)
+            ]
+        );
+        let files: Vec<(&str, Option<&str>)> = full
+            .large_items
+            .iter()
+            .filter(|item| !item.is_dir)
+            .map(|item| (item.name.as_str(), item.category_id.as_deref()))
+            .collect();
+        assert_eq!(files, [("movie.mp4", None), ("big.bin", Some("cache"))]);
+    }
+
+    #[test]
+    fn hard_linked_files_count_once_and_reclaim_only_when_every_link_is_inside() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = dir.path().join("shared");
+        let partial = dir.path().join("partial");
+        fs::create_dir_all(&shared).unwrap();
+        fs::create_dir_all(&partial).unwrap();
+        fs::write(shared.join("a.bin"), vec![0; 1000]).unwrap();
+        fs::hard_link(shared.join("a.bin"), shared.join("b.bin")).unwrap();
+        fs::write(partial.join("c.bin"), vec![0; 700]).unwrap();
+        fs::hard_link(partial.join("c.bin"), dir.path().join("outside.bin")).unwrap();
+        let categories = vec![
+            CategoryDef::for_roots("shared", vec![shared]),
+            CategoryDef::for_roots("partial", vec![partial]),
+        ];
+
+        let op = Operation::detached("test");
+        let result = scan_cleanup_items_sync(&categories, 1, false, &op).unwrap();
+        let totals: Vec<(&str, u64, u64)> = result
+            .iter()
+            .map(|category| {
+                (
+                    category.id.as_str(),
+                    category.size_bytes,
+                    category.reclaimable_bytes,
+                )
+            })
+            .collect();
+        assert_eq!(totals, [("shared", 1000, 1000), ("partial", 700, 0)]);
+    }
+
+    #[test]
+    fn unreadable_roots_are_reported_and_missing_ones_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good");
+        fs::create_dir(&good).unwrap();
+        fs::write(good.join("a.tmp"), [0; 100]).unwrap();
+        fs::write(dir.path().join("file"), [0; 10]).unwrap();
+        let unreadable = dir.path().join("file").join("child");
+        let categories = vec![CategoryDef::for_roots(
+            "mixed",
+            vec![dir.path().join("missing"), unreadable.clone(), good],
+        )];
+
+        let op = Operation::detached("test");
+        let result = scan_cleanup_items_sync(&categories, 1, false, &op).unwrap();
+        let category = &result[0];
+        assert_eq!((category.size_bytes, category.inaccessible_count), (100, 1));
+        assert_eq!(category.errors.len(), 1);
+        assert_eq!(category.errors[0].root, unreadable.to_string_lossy());
+        assert_eq!(
+            category.errors[0].errors[0].path,
+            unreadable.to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn error_samples_are_capped_but_every_error_is_counted() {
+        let root = Path::new("root");
+        let error = |index: usize| ScanError {
+            path: format!("root/{}", index),
+            kind: ScanErrorKind::PermissionDenied,
+            message: "denied".to_string(),
+        };
+        let mut first = CategoryScan::default();
+        let mut second = CategoryScan::default();
+        for index in 0..MAX_SCAN_ERROR_SAMPLES {
+            first.add_error(root, error(index));
+            second.add_error(root, error(MAX_SCAN_ERROR_SAMPLES + index));
+        }
+        first.merge(second);
+
+        assert_eq!(first.errors.len(), 1);
+        assert_eq!(first.errors[0].count, 2 * MAX_SCAN_ERROR_SAMPLES as u64);
+        assert_eq!(first.errors[0].errors.len(), MAX_SCAN_ERROR_SAMPLES);
+        assert_eq!(first.errors[0].errors[0].path, "root/0");
+    }
+
+    #[test]
+    fn breakdown_splits_totals_by_root_extension_and_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        let media = dir.path().join("media");
+        fs::create_dir(&logs).unwrap();
+        fs::create_dir(&media).unwrap();
+        let write_aged = |path: PathBuf, size: usize, days: u64| {
+            fs::write(&path, vec![0; size]).unwrap();
+            let modified = SystemTime::now() - std::time::Duration::from_secs(days * 86_400);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(modified))
+                .unwrap();
+        };
+        write_aged(logs.join("a.log"), 100, 0);
+        write_aged(logs.join("b.LOG"), 50, 10);
+        write_aged(logs.join("c.tmp"), 30, 100);
+        write_aged(media.join("x.bin"), 400, 3);
+        let categories = vec![CategoryDef::for_roots(
+            "mixed",
+            vec![logs.clone(), media.clone()],
+        )];
+
+        let op = Operation::detached("test");
+        let result = scan_cleanup_items_sync(&categories, 2, false, &op).unwrap();
+        let entries = |entries: &[BreakdownEntry]| -> Vec<(String, u64, u64)> {
+            entries
+                .iter()
+                .map(|entry| (entry.key.clone(), entry.size_bytes, entry.file_count))
+                .collect()
+        };
+        let breakdown = &result[0].breakdown;
+        let root = |path: &Path| path.to_string_lossy().to_string();
+        assert_eq!(
+            entries(&breakdown.roots),
+            [(root(&media), 400, 1), (root(&logs), 180, 3)]
+        );
+        assert_eq!(
+            entries(&breakdown.extensions),
+            [
+                (".bin".to_string(), 400, 1),
+                (".log".to_string(), 150, 2),
+                (".tmp".to_string(), 30, 1)
+            ]
+        );
+        assert_eq!(
+            entries(&breakdown.ages),
+            [
+                ("day".to_string(), 100, 1),
+                ("week".to_string(), 400, 1),
+                ("month".to_string(), 50, 1),
+                ("older".to_string(), 30, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn rare_extensions_fold_into_one_entry() {
+        let mut scan = CategoryScan::default();
+        for index in 0..MAX_BREAKDOWN_EXTENSIONS + 5 {
+            let extension = format!(".e{}", index);
+            scan.files
+                .add(&extension, UNKNOWN_HOUR, 100 + index as u64, 1);
+        }
+
+        let extensions = scan.breakdown().extensions;
+        assert_eq!(extensions.len(), MAX_BREAKDOWN_EXTENSIONS);
+        let rest = extensions.last().unwrap();
+        assert_eq!((rest.key.as_str(), rest.file_count), ("*", 6));
+        assert_eq!(rest.size_bytes, (100..106).sum::<u64>());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::{
+    normalize_path_str, operation::Operation, to_unix_ms, write_json, CleanupCategory, LargeItem,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tauri::{AppHandle, Manager};
+
+const SNAPSHOT_DIR: &str = "snapshots";
+const MAX_SNAPSHOTS: usize = 50;
+const SNAPSHOT_KEEP_RECENT: usize = 10;
+pub(crate) const SNAPSHOT_DIR_DEPTH: usize = 2;
+pub(crate) const DEFAULT_DIFF_LIMIT: usize = 500;
+
+/// Category entries use the category id as their path.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanSnapshot {
+    id: String,
+    created_ms: i64,
+    source: String,
+    volume: Option<String>,
+    entries: Vec<SnapshotEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotEntry {
+    pub(crate) kind: SnapshotKind,
+    pub(crate) path: String,
+    pub(crate) size_bytes: u64,
+}
+
+/// `File` and `Dir` only cover a scan's top items; the others are complete.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum SnapshotKind {
+    Category,
+    File,
+    Dir,
+    Folder,
+}
+
+impl SnapshotKind {
+    fn is_complete(self) -> bool {
+        matches!(self, SnapshotKind::Category | SnapshotKind::Folder)
+    }
+}
+
+/// Stored beside each snapshot as `<id>.summary.json`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotSummary {
+    id: String,
+    created_ms: i64,
+    source: String,
+    volume: Option<String>,
+    entry_count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ScanDiff {
+    from: SnapshotSummary,
+    to: SnapshotSummary,
+    changes: Vec<PathChange>,
+    total_count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PathChange {
+    kind: SnapshotKind,
+    path: String,
+    change: ChangeKind,
+    before_bytes: u64,
+    after_bytes: u64,
+    delta_bytes: i64,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ChangeKind {
+    Grew,
+    Appeared,
+    Shrank,
+    Removed,
+}
+
+pub(crate) fn snapshot_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(SNAPSHOT_DIR))
+        .map_err(|err| err.to_string())
+}
+
+pub(crate) fn category_entries(categories: &[CleanupCategory]) -> Vec<SnapshotEntry> {
+    categories
+        .iter()
+        .map(|category| SnapshotEntry {
+            kind: SnapshotKind::Category,
+            path: category.id.clone(),
+            size_bytes: category.size_bytes,
+        })
+        .collect()
+}
+
+pub(crate) fn large_item_entries(items: &[LargeItem]) -> Vec<SnapshotEntry> {
+    items
+        .iter()
+        .map(|item| SnapshotEntry {
+            kind: if item.is_dir {
+                SnapshotKind::Dir
+            } else {
+                SnapshotKind::File
+            },
+            path: item.path.clone(),
+            size_bytes: item.size_bytes,
+        })
+        .collect()
+}
+
+pub(crate) fn save_snapshot(op: &Operation, volume: Option<&Path>, entries: Vec<SnapshotEntry>) {
+    if let Some(Ok(dir)) = op.app().map(snapshot_dir) {
+        write_snapshot(&dir, op.progress.operation, volume, entries);
+    }
+}
+
+fn write_snapshot(
+    dir: &Path,
+    source: &str,
+    volume: Option<&Path>,
+    mut entries: Vec<SnapshotEntry>,
+) {
+    if fs::create_dir_all(dir).is_err() {
+        return;
+    }
+    let mut created_ms = to_unix_ms(SystemTime::now()).unwrap_or(0);
+    while dir.join(format!("{}.json", created_ms)).exists() {
+        created_ms += 1;
+    }
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert((entry.kind, normalize_path_str(&entry.path))));
+    let snapshot = ScanSnapshot {
+        id: created_ms.to_string(),
+        created_ms,
+        source: source.to_string(),
+        volume: volume.map(|volume| volume.to_string_lossy().to_string()),
+        entries,
+    };
+    let path = dir.join(format!("{}.json", snapshot.id));
+    let summary_path = dir.join(format!("{}.summary.json", snapshot.id));
+    let saved =
+        write_json(&path, &snapshot).and_then(|_| write_json(&summary_path, &snapshot.summary()));
+    if saved.is_err() {
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&summary_path);
+        return;
+    }
+    prune_snapshots(dir);
+}
+
+/// Per source and volume: the newest few plus the newest of each earlier day.
+fn prune_snapshots(dir: &Path) {
+    let mut groups: HashMap<(String, Option<String>), Vec<SnapshotSummary>> = HashMap::new();
+    for summary in list_summaries(dir) {
+        groups
+            .entry((summary.source.clone(), summary.volume.clone()))
+            .or_default()
+            .push(summary);
+    }
+    for summaries in groups.into_values() {
+        let mut kept = 0;
+        let mut days = HashSet::new();
+        for (position, summary) in summaries.iter().enumerate() {
+            let day = summary.created_ms.div_euclid(24 * 60 * 60 * 1000);
+            let first_of_day = days.insert(day);
+            if kept < MAX_SNAPSHOTS && (position < SNAPSHOT_KEEP_RECENT || first_of_day) {
+                kept += 1;
+                continue;
+            }
+            let _ = fs::remove_file(dir.join(format!("{}.json", summary.id)));
+            let _ = fs::remove_file(dir.join(format!("{}.summary.json", summary.id)));
+        }
+    }
+}
+
+fn list_summaries(dir: &Path) -> Vec<SnapshotSummary> {
+    snapshot_ids(dir)
+        .into_iter()
+        .filter_map(|id| {
+            let summary = File::open(dir.join(format!("{}.summary.json", id)))
+                .ok()
+                .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok());
+            summary.or_else(|| {
+                load_snapshot(dir, &id.to_string())
+                    .ok()
+                    .map(|snapshot| snapshot.summary())
+            })
+        })
+        .collect()
+}
+
+fn snapshot_ids(dir: &Path) -> Vec<i64> {
+    let mut ids: Vec<i64> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.strip_suffix(".json")?.parse().ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort_by_key(|id| Reverse(*id));
+    ids
+}
+
+fn load_snapshot(dir: &Path, id: &str) -> Result<ScanSnapshot, String> {
+    let id: i64 = id
+        .parse()
+        .map_err(|_| format!("Invalid snapshot id: {}", id))?;
+    let file = File::open(dir.join(format!("{}.json", id)))
+        .map_err(|_| format!("Snapshot {} not found.", id))?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|err| err.to_string())
+}
+
+impl ScanSnapshot {
+    fn summary(&self) -> SnapshotSummary {
+        SnapshotSummary {
+            id: self.id.clone(),
+            created_ms: self.created_ms,
+            source: self.source.clone(),
+            volume: self.volume.clone(),
+            entry_count: self.entries.len(),
+        }
+    }
+}
+
+pub(crate) fn list_snapshots_sync(dir: &Path) -> Vec<SnapshotSummary> {
+    list_summaries(dir)
+}
+
+pub(crate) fn diff_scans_sync(
+    dir: &Path,
+    a: &str,
+    b: &str,
+    limit: usize,
+) -> Result<ScanDiff, String> {
+    let before = load_snapshot(dir, a)?;
+    let after = load_snapshot(dir, b)?;
+    if before.source != after.source || before.volume != after.volume {
+        return Err("Snapshots come from different scans or volumes.".to_string());
+    }
+
+    let mut previous: HashMap<(SnapshotKind, String), &SnapshotEntry> = before
+        .entries
+        .iter()
+        .map(|entry| ((entry.kind, normalize_path_str(&entry.path)), entry))
+        .collect();
+    let mut changes = Vec::new();
+    for entry in &after.entries {
+        let key = (entry.kind, normalize_path_str(&entry.path));
+        let before_bytes = previous.remove(&key).map(|entry| entry.size_bytes);
+        let change = match before_bytes {
+            None if entry.kind.is_complete() => ChangeKind::Appeared,
+            None => continue,
+            Some(size) if entry.size_bytes > size => ChangeKind::Grew,
+            Some(size) if entry.size_bytes < size => ChangeKind::Shrank,
+            Some(_) => continue,
+        };
+        changes.push(PathChange {
+            kind: entry.kind,
+            path: entry.path.clone(),
+            change,
+            before_bytes: before_bytes.unwrap_or(0),
+            after_bytes: entry.size_bytes,
+            delta_bytes: entry.size_bytes as i64 - before_bytes.unwrap_or(0) as i64,
+        });
+    }
+    changes.extend(
+        previous
+            .into_values()
+            .filter(|entry| entry.kind.is_complete())
+            .map(|entry| PathChange {
+                kind: entry.kind,
+                path: entry.path.clone(),
+                change: ChangeKind::Removed,
+                before_bytes: entry.size_bytes,
+                after_bytes: 0,
+                delta_bytes: -(entry.size_bytes as i64),
+            }),
+    );
+    changes.sort_by(|a, b| {
+        b.delta_bytes
+            .cmp(&a.delta_bytes)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let total_count = changes.len();
+    changes.truncate(limit);
+    Ok(ScanDiff {
+        from: before.summary(),
+        to: after.summary(),
+        changes,
+        total_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: SnapshotKind, path: &str, size_bytes: u64) -> SnapshotEntry {
+        SnapshotEntry {
+            kind,
+            path: path.to_string(),
+            size_bytes,
+        }
+    }
+
+    /// Ids of the snapshots written so far, oldest first.
+    fn ids(dir: &Path) -> Vec<String> {
+        let mut ids = snapshot_ids(dir);
+        ids.reverse();
+        ids.iter().map(i64::to_string).collect()
+    }
+
+    #[test]
+    fn diff_sorts_by_growth_and_skips_top_items_that_fell_off() {
+        use SnapshotKind::{Category, File, Folder};
+
+        let dir = tempfile::tempdir().unwrap();
+        let volume = Some(Path::new("C:\\"));
+        write_snapshot(
+            dir.path(),
+            "scanFull",
+            volume,
+            vec![
+                entry(Category, "temp", 100),
+                entry(Category, "logs", 500),
+                entry(Folder, "C:\\Old", 300),
+                entry(File, "C:\\gone.iso", 900),
+            ],
+        );
+        write_snapshot(
+            dir.path(),
+            "scanFull",
+            volume,
+            vec![
+                entry(Category, "temp", 700),
+                entry(Category, "logs", 200),
+                entry(Folder, "C:\\New", 50),
+                entry(File, "C:\\new.iso", 800),
+            ],
+        );
+
+        let ids = ids(dir.path());
+        let diff = diff_scans_sync(dir.path(), &ids[0], &ids[1], 10).unwrap();
+        let changes: Vec<(&str, i64)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.delta_bytes))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("temp", 600),
+                ("C:\\New", 50),
+                ("C:\\Old", -300),
+                ("logs", -300)
+            ]
+        );
+        let limited = diff_scans_sync(dir.path(), &ids[0], &ids[1], 1).unwrap();
+        assert_eq!((limited.changes.len(), limited.total_count), (1, 4));
+    }
+
+    #[test]
+    fn diff_refuses_snapshots_from_another_scan_or_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let totals = || vec![entry(SnapshotKind::Category, "temp", 100)];
+        write_snapshot(dir.path(), "scanFull", Some(Path::new("C:\\")), totals());
+        write_snapshot(dir.path(), "scanFull", Some(Path::new("D:\\")), totals());
+        write_snapshot(dir.path(), "scanCleanup", Some(Path::new("C:\\")), totals());
+
+        let ids = ids(dir.path());
+        for other in &ids[1..] {
+            assert_eq!(
+                diff_scans_sync(dir.path(), &ids[0], other, 10)
+                    .err()
+                    .as_deref(),
+                Some("Snapshots come from different scans or volumes.")
+            );
+        }
+        assert!(diff_scans_sync(dir.path(), &ids[0], &ids[0], 10).is_ok());
+    }
+}
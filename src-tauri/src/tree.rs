@@ -0,0 +1,212 @@
+use crate::{operation::Operation, scan::run_parallel, to_unix_ms, CANCELLED_MESSAGE};
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub(crate) const DEFAULT_TREE_DEPTH: u32 = 2;
+pub(crate) const MAX_TREE_DEPTH: u32 = 8;
+const MAX_TREE_CHILDREN: usize = 200;
+
+/// Sizes cover the whole subtree; `children` holds the largest entries only.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TreeNode {
+    path: String,
+    name: String,
+    is_dir: bool,
+    size_bytes: u64,
+    file_count: u64,
+    newest_modified_ms: Option<i64>,
+    inaccessible_count: u64,
+    children: Vec<TreeNode>,
+    omitted_children: u64,
+}
+
+pub(crate) fn analyze_tree_sync(
+    root: &Path,
+    depth: usize,
+    threads: usize,
+    op: &Operation,
+) -> Result<TreeNode, String> {
+    let metadata = fs::symlink_metadata(root).map_err(|err| err.to_string())?;
+    if !metadata.is_dir() {
+        return Ok(TreeNode::file(root, &metadata));
+    }
+    op.progress.enter(None, root);
+    let mut node = TreeNode::dir(root);
+    let subdirs = node.read_entries(root, depth > 0, op);
+    let children = run_parallel(&subdirs, threads, |dir| {
+        tree_node(dir, depth.saturating_sub(1), op)
+    });
+    for child in children {
+        node.add_child(child, depth > 0);
+    }
+    node.finish();
+    if op.is_cancelled() {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+    Ok(node)
+}
+
+fn tree_node(dir: &Path, depth: usize, op: &Operation) -> TreeNode {
+    let mut node = TreeNode::dir(dir);
+    for subdir in node.read_entries(dir, depth > 0, op) {
+        if op.is_cancelled() {
+            break;
+        }
+        let child = tree_node(&subdir, depth.saturating_sub(1), op);
+        node.add_child(child, depth > 0);
+    }
+    node.finish();
+    node
+}
+
+impl TreeNode {
+    fn dir(path: &Path) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string_lossy().to_string()),
+            is_dir: true,
+            size_bytes: 0,
+            file_count: 0,
+            newest_modified_ms: None,
+            inaccessible_count: 0,
+            children: Vec::new(),
+            omitted_children: 0,
+        }
+    }
+
+    fn file(path: &Path, metadata: &fs::Metadata) -> Self {
+        Self {
+            is_dir: false,
+            size_bytes: metadata.len(),
+            file_count: 1,
+            newest_modified_ms: metadata.modified().ok().and_then(to_unix_ms),
+            ..Self::dir(path)
+        }
+    }
+
+    /// Symlinks and junctions are not followed.
+    fn read_entries(&mut self, dir: &Path, keep_files: bool, op: &Operation) -> Vec<PathBuf> {
+        let mut subdirs = Vec::new();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                self.inaccessible_count += 1;
+                return subdirs;
+            }
+        };
+        for entry in entries {
+            if op.is_cancelled() {
+                break;
+            }
+            let Ok(entry) = entry else {
+                self.inaccessible_count += 1;
+                continue;
+            };
+            let Ok(file_type) = entry.file_type() else {
+                self.inaccessible_count += 1;
+                continue;
+            };
+            if file_type.is_dir() {
+                subdirs.push(entry.path());
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            match entry.metadata() {
+                Ok(metadata) => {
+                    let path = entry.path();
+                    op.progress.add_file(&path, metadata.len());
+                    self.add_child(TreeNode::file(&path, &metadata), keep_files);
+                }
+                Err(_) => self.inaccessible_count += 1,
+            }
+        }
+        subdirs
+    }
+
+    fn add_child(&mut self, child: TreeNode, keep: bool) {
+        self.size_bytes += child.size_bytes;
+        self.file_count += child.file_count;
+        self.inaccessible_count += child.inaccessible_count;
+        self.newest_modified_ms = self.newest_modified_ms.max(child.newest_modified_ms);
+        if keep {
+            self.children.push(child);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.children.sort_by_key(|child| Reverse(child.size_bytes));
+        if self.children.len() > MAX_TREE_CHILDREN {
+            self.omitted_children = (self.children.len() - MAX_TREE_CHILDREN) as u64;
+            self.children.truncate(MAX_TREE_CHILDREN);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_below_the_depth_keep_totals_but_drop_children() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("a").join("sub")).unwrap();
+        fs::create_dir(root.path().join("b")).unwrap();
+        fs::write(root.path().join("big.bin"), vec![0; 500]).unwrap();
+        fs::write(root.path().join("a").join("x.bin"), [0; 100]).unwrap();
+        fs::write(root.path().join("a").join("sub").join("y.bin"), [0; 50]).unwrap();
+        fs::write(root.path().join("b").join("z.bin"), [0; 300]).unwrap();
+
+        let op = Operation::detached("test");
+        let tree = analyze_tree_sync(root.path(), 1, 2, &op).unwrap();
+        assert_eq!((tree.size_bytes, tree.file_count), (950, 4));
+        let children: Vec<(&str, bool, u64, u64, usize)> = tree
+            .children
+            .iter()
+            .map(|child| {
+                (
+                    child.name.as_str(),
+                    child.is_dir,
+                    child.size_bytes,
+                    child.file_count,
+                    child.children.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            children,
+            [
+                ("big.bin", false, 500, 1, 0),
+                ("b", true, 300, 1, 0),
+                ("a", true, 150, 2, 0),
+            ]
+        );
+        let newest = tree.children.iter().map(|child| child.newest_modified_ms);
+        assert_eq!(tree.newest_modified_ms, newest.max().flatten());
+    }
+
+    #[test]
+    fn wide_folders_keep_the_largest_children() {
+        let root = tempfile::tempdir().unwrap();
+        for index in 0..MAX_TREE_CHILDREN + 3 {
+            fs::write(root.path().join(format!("{}.bin", index)), vec![0; index]).unwrap();
+        }
+
+        let op = Operation::detached("test");
+        let tree = analyze_tree_sync(root.path(), 1, 1, &op).unwrap();
+        assert_eq!(tree.file_count, MAX_TREE_CHILDREN as u64 + 3);
+        assert_eq!(tree.children.len(), MAX_TREE_CHILDREN);
+        assert_eq!(tree.omitted_children, 3);
+        assert_eq!(tree.children[0].size_bytes, MAX_TREE_CHILDREN as u64 + 2);
+        assert_eq!(tree.children.last().unwrap().size_bytes, 3);
+    }
+}
@@ -1,6 +1,7 @@
 import { useEffect, useMemo, useState } from "react";
 import type { CSSProperties } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { revealItemInDir } from "@tauri-apps/plugin-opener";
 import { check } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
@@ -8,21 +9,100 @@ import "./App.css";
 
 type DiskInfo = {
   mountPoint: string;
+  name: string;
+  fileSystem: string;
+  removable: boolean;
   totalBytes: number;
   freeBytes: number;
   usedBytes: number;
   usedPercent: number;
 };
 
+type DiskHistory = {
+  mountPoint: string;
+  samples: {
+    timeMs: number;
+    totalBytes: number;
+    usedBytes: number;
+    freeBytes: number;
+  }[];
+  growthBytesPerDay?: number | null;
+  fullAtMs?: number | null;
+  daysUntilFull?: number | null;
+};
+
 type CleanupCategory = {
   id: string;
   title: string;
   description: string;
   sizeBytes: number;
+  allocatedBytes: number;
+  reclaimableBytes: number;
+  fileCount: number;
+  inaccessibleCount: number;
+  errors: RootScanErrors[];
+  breakdown: CategoryBreakdown;
+  retentionDays?: number | null;
+};
+
+type BrowserCacheItem = {
+  name: string;
+  path: string;
+  sizeBytes: number;
   fileCount: number;
 };
 
-type CleanupItem = {
+type BrowserProfileCache = {
+  name: string;
+  path: string;
+  sizeBytes: number;
+  fileCount: number;
+  items: BrowserCacheItem[];
+};
+
+type BrowserCache = {
+  browser: string;
+  sizeBytes: number;
+  fileCount: number;
+  profiles: BrowserProfileCache[];
+};
+
+type CategoryOptions = {
+  retentionDays?: number;
+};
+
+type BreakdownEntry = {
+  key: string;
+  sizeBytes: number;
+  fileCount: number;
+};
+
+type CategoryBreakdown = {
+  roots: BreakdownEntry[];
+  extensions: BreakdownEntry[];
+  ages: BreakdownEntry[];
+};
+
+type RootScanErrors = {
+  root: string;
+  count: number;
+  errors: {
+    path: string;
+    kind: "permissionDenied" | "notFound" | "other";
+    message: string;
+  }[];
+};
+
+type ItemDetails = {
+  allocatedBytes: number;
+  createdMs?: number | null;
+  accessedMs?: number | null;
+  owner?: string | null;
+  readOnly: boolean;
+  hidden: boolean;
+};
+
+type CleanupItem = ItemDetails & {
   path: string;
   sizeBytes: number;
   modifiedMs?: number | null;
@@ -34,26 +114,62 @@ type HibernationInfo = {
   path: string;
 };
 
-type LargeItem = {
+type LargeItem = ItemDetails & {
   path: string;
   name: string;
   sizeBytes: number;
+  reclaimableBytes: number;
+  modifiedMs?: number | null;
   isDir: boolean;
   suspicious: boolean;
+  score: number;
+  reasons: string[];
   categoryId?: string | null;
 };
 
+type FullScan = {
+  categories: CleanupCategory[];
+  largeItems: LargeItem[];
+  largeItemErrors: RootScanErrors[];
+};
+
 type UpdateStatus = 'idle' | 'checking' | 'downloading' | 'ready' | 'installing';
 
+type ListSort = "size" | "modified" | "name" | "extension";
+
+type ListQuery = {
+  sort?: ListSort;
+  order?: "asc" | "desc";
+  minSizeBytes?: number;
+  olderThanDays?: number;
+  newerThanDays?: number;
+  extensions?: string[];
+  nameContains?: string;
+};
+
 type CategoryItems = {
   items: CleanupItem[];
   hasMore: boolean;
+  nextCursor?: string | null;
+  totalCount: number;
+  inaccessibleCount: number;
+};
+
+type ProgressEvent = {
+  operation: string;
+  categoryId?: string | null;
+  currentRoot?: string | null;
+  files: number;
+  bytes: number;
+  largest: { path: string; sizeBytes: number }[];
+  done: boolean;
 };
 
 type CleanupResult = {
   deletedBytes: number;
   deletedCount: number;
   failed: { path: string; message: string }[];
+  cancelled: boolean;
 };
 
 const CATEGORY_ACCENTS: Record<string, string> = {
@@ -66,6 +182,8 @@ const CATEGORY_ACCENTS: Record<string, string> = {
   windows_old: "#ff6b6b",
 };
 
+const CANCELLED_MESSAGE = "Operation cancelled.";
+
 const formatBytes = (bytes: number) => {
   if (!bytes || bytes < 0) return "0 B";
   const units = ["B", "KB", "MB", "GB", "TB"];
@@ -77,6 +195,22 @@ const formatBytes = (bytes: number) => {
   return `${value.toFixed(value >= 100 || index === 0 ? 0 : 2)} ${units[index]}`;
 };
 
+const AGE_LABELS: Record<string, string> = {
+  day: "1 天内",
+  week: "7 天内",
+  month: "30 天内",
+  older: "更早",
+};
+
+const formatBreakdown = (entries: BreakdownEntry[], label: (key: string) => string) =>
+  entries
+    .filter((entry) => entry.sizeBytes > 0)
+    .slice(0, 4)
+    .map((entry) => `${label(entry.key)} ${formatBytes(entry.sizeBytes)}`)
+    .join(" · ");
+
+const RETENTION_CHOICES = [7, 30, 90, 180];
+
 const formatDate = (ms?: number | null) => {
   if (!ms) return "未知时间";
   const formatter = new Intl.DateTimeFormat("zh-CN", {
@@ -102,6 +236,10 @@ function App() {
   const [detailItems, setDetailItems] = useState<CleanupItem[]>([]);
   const [detailsLoading, setDetailsLoading] = useState(false);
   const [detailsHasMore, setDetailsHasMore] = useState(false);
+  const [detailsCursor, setDetailsCursor] = useState<string | null>(null);
+  const [detailsTotal, setDetailsTotal] = useState(0);
+  const [detailsLoadingMore, setDetailsLoadingMore] = useState(false);
+  const [detailsSort, setDetailsSort] = useState<ListSort>("size");
   const [excludedPaths, setExcludedPaths] = useState<Record<string, string[]>>(
     {},
   );
@@ -114,8 +252,17 @@ function App() {
   const [includedSizes, setIncludedSizes] = useState<Record<string, number>>(
     {},
   );
+  const [categoryOptions, setCategoryOptions] = useState<
+    Record<string, CategoryOptions>
+  >({});
+  const [browserCaches, setBrowserCaches] = useState<BrowserCache[]>([]);
   const [largeItems, setLargeItems] = useState<LargeItem[]>([]);
   const [largeScanning, setLargeScanning] = useState(false);
+  const [disks, setDisks] = useState<DiskInfo[]>([]);
+  const [diskHistory, setDiskHistory] = useState<DiskHistory | null>(null);
+  const [largeVolume, setLargeVolume] = useState<string | null>(null);
+  const [forceRescan, setForceRescan] = useState(false);
+  const [runningOperation, setRunningOperation] = useState<string | null>(null);
   const [largeSelectedPaths, setLargeSelectedPaths] = useState<string[]>([]);
   const [showSuspiciousOnly, setShowSuspiciousOnly] = useState(false);
   const [scanProgress, setScanProgress] = useState(0);
@@ -136,6 +283,20 @@ function App() {
       });
   }, []);
 
+  useEffect(() => {
+    invoke<DiskInfo[]>("list_disks")
+      .then(setDisks)
+      .catch((err) => {
+        setError(String(err));
+      });
+  }, []);
+
+  useEffect(() => {
+    invoke<DiskHistory>("get_disk_history")
+      .then(setDiskHistory)
+      .catch(() => setDiskHistory(null));
+  }, []);
+
   useEffect(() => {
     invoke<HibernationInfo>("get_hibernation_info")
       .then(setHibernationInfo)
@@ -144,6 +305,21 @@ function App() {
       });
   }, []);
 
+  useEffect(() => {
+    const unlisten = listen<ProgressEvent>("operation-progress", (event) => {
+      const progress = event.payload;
+      if (progress.done) return;
+      const verb = progress.operation.startsWith("clean") ? "正在清理" : "正在扫描";
+      const location = progress.currentRoot ? ` ${progress.currentRoot}` : "";
+      setScanStatus(
+        `${verb}${location} · ${progress.files} 个文件 · ${formatBytes(progress.bytes)}`,
+      );
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
   // Check for updates on startup
   useEffect(() => {
     const checkForUpdate = async () => {
@@ -270,54 +446,126 @@ function App() {
     return includedPaths[activeCategory.id]?.length ?? 0;
   }, [activeCategory, includedPaths]);
 
-  const handleScan = async () => {
+  // Ids passed to long-running commands, so `cancel_operation` can stop them.
+  const startOperation = () => {
+    const id = `${Date.now()}-${Math.random().toString(36).slice(2)}`;
+    setRunningOperation(id);
+    return id;
+  };
+
+  const handleStop = () => {
+    if (!runningOperation) return;
+    invoke<boolean>("cancel_operation", { operationId: runningOperation }).catch(
+      (err) => setError(String(err)),
+    );
+  };
+
+  const applyLargeItems = (items: LargeItem[]) => {
+    setLargeItems(items);
+    const standalonePaths = new Set(
+      items.filter((item) => !item.categoryId).map((item) => item.path),
+    );
+    setLargeSelectedPaths((prev) =>
+      prev.filter((path) => standalonePaths.has(path)),
+    );
+  };
+
+  const applyCategories = (items: CleanupCategory[]) => {
+    const sorted = [...items].sort((a, b) => b.sizeBytes - a.sizeBytes);
+    setCategories(sorted);
+    setSelectedIds([]);
+    setExcludedPaths({});
+    setExcludedSizes({});
+    setIncludedPaths({});
+    setIncludedSizes({});
+    invoke<BrowserCache[]>("scan_browser_caches")
+      .then(setBrowserCaches)
+      .catch(() => setBrowserCaches([]));
+    return sorted;
+  };
+
+  const reportScanError = (err: unknown) => {
+    if (String(err).includes(CANCELLED_MESSAGE)) {
+      setScanStatus("扫描已取消");
+    } else {
+      setError(String(err));
+      setScanStatus("扫描失败，请稍后重试");
+    }
+  };
+
+  const handleScan = async (options = categoryOptions) => {
     setScanning(true);
     setError("");
-    setScanStatus("正在分析磁盘…");
+    setScanStatus("正在扫描可清理文件…");
+    const operationId = startOperation();
     try {
       const [disk, items] = await Promise.all([
         invoke<DiskInfo>("get_disk_info"),
-        invoke<CleanupCategory[]>("scan_cleanup_items"),
+        invoke<CleanupCategory[]>("scan_cleanup_items", {
+          operationId,
+          options,
+          forceRescan,
+        }),
       ]);
-      const sorted = [...items].sort((a, b) => b.sizeBytes - a.sizeBytes);
       setDiskInfo(disk);
-      setCategories(sorted);
-      setSelectedIds([]);
-      setExcludedPaths({});
-      setExcludedSizes({});
-      setIncludedPaths({});
-      setIncludedSizes({});
+      const sorted = applyCategories(items);
       setScanStatus(`扫描完成，发现 ${sorted.length} 项可清理`);
     } catch (err) {
-      setError(String(err));
-      setScanStatus("扫描失败，请稍后重试");
+      reportScanError(err);
     } finally {
+      setRunningOperation(null);
       setScanning(false);
     }
   };
 
-  const handleLargeScan = async () => {
+  // One pass over the selected volume fills both the large-item list and the
+  // cleanup categories.
+  const handleFullScan = async () => {
+    setScanning(true);
     setLargeScanning(true);
     setError("");
-    setScanStatus("正在扫描大文件…");
+    setScanStatus("正在分析磁盘…");
+    const operationId = startOperation();
     try {
-      const items = await invoke<LargeItem[]>("scan_large_items");
-      setLargeItems(items);
-      const standalonePaths = new Set(
-        items.filter((item) => !item.categoryId).map((item) => item.path),
+      const [disk, result] = await Promise.all([
+        invoke<DiskInfo>("get_disk_info"),
+        invoke<FullScan>("scan_full", {
+          operationId,
+          mountPoint: largeVolume,
+          options: categoryOptions,
+          forceRescan,
+        }),
+      ]);
+      setDiskInfo(disk);
+      const sorted = applyCategories(result.categories);
+      applyLargeItems(result.largeItems);
+      const unreadable = result.largeItemErrors.reduce(
+        (sum, errors) => sum + errors.count,
+        0,
       );
-      setLargeSelectedPaths((prev) =>
-        prev.filter((path) => standalonePaths.has(path)),
+      setScanStatus(
+        `扫描完成，发现 ${sorted.length} 项可清理，${result.largeItems.length} 项大文件/文件夹` +
+          (unreadable ? `，${unreadable} 项无法读取` : ""),
       );
-      setScanStatus(`扫描完成，发现 ${items.length} 项大文件/文件夹`);
     } catch (err) {
-      setError(String(err));
-      setScanStatus("扫描失败，请稍后重试");
+      reportScanError(err);
     } finally {
+      setRunningOperation(null);
+      setScanning(false);
       setLargeScanning(false);
     }
   };
 
+  const changeRetention = (id: string, retentionDays: number) => {
+    const next = {
+      ...categoryOptions,
+      [id]: { ...categoryOptions[id], retentionDays },
+    };
+    setCategoryOptions(next);
+    handleScan(next);
+  };
+
+
   const handleHibernationToggle = async () => {
     if (hibernationLoading || !hibernationInfo) return;
     const nextEnabled = !hibernationInfo.enabled;
@@ -364,31 +612,62 @@ function App() {
     setIncludedSizes({});
   };
 
-  const openDetails = async (category: CleanupCategory) => {
+  const openDetails = async (
+    category: CleanupCategory,
+    sort: ListSort = detailsSort,
+  ) => {
     setActiveCategory(category);
     setDetailsOpen(true);
     setDetailsLoading(true);
+    setDetailsSort(sort);
     try {
+      const query: ListQuery = { sort };
       const response = await invoke<CategoryItems>("list_category_items", {
         id: category.id,
         limit: 300,
+        query,
+        options: categoryOptions[category.id],
       });
       setDetailItems(response.items);
       setDetailsHasMore(response.hasMore);
+      setDetailsCursor(response.nextCursor ?? null);
+      setDetailsTotal(response.totalCount);
     } catch (err) {
       setDetailItems([]);
       setDetailsHasMore(false);
+      setDetailsCursor(null);
       setError(String(err));
     } finally {
       setDetailsLoading(false);
     }
   };
 
+  const loadMoreDetails = async () => {
+    if (!activeCategory || !detailsCursor) return;
+    setDetailsLoadingMore(true);
+    try {
+      const response = await invoke<CategoryItems>("list_category_items", {
+        id: activeCategory.id,
+        limit: 300,
+        cursor: detailsCursor,
+        options: categoryOptions[activeCategory.id],
+      });
+      setDetailItems((prev) => [...prev, ...response.items]);
+      setDetailsHasMore(response.hasMore);
+      setDetailsCursor(response.nextCursor ?? null);
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setDetailsLoadingMore(false);
+    }
+  };
+
   const closeDetails = () => {
     setDetailsOpen(false);
     setActiveCategory(null);
     setDetailItems([]);
     setDetailsHasMore(false);
+    setDetailsCursor(null);
   };
 
   const handleReveal = async (path: string) => {
@@ -497,38 +776,34 @@ function App() {
     setScanStatus("正在清理中，请保持应用打开…");
     setError("");
     const hadLargeSelection = largeSelectedPaths.length > 0;
-    const categoryStats = categories.reduce<
-      Record<string, { sizeBytes: number; fileCount: number }>
-    >((acc, category) => {
-      acc[category.id] = {
-        sizeBytes: category.sizeBytes,
-        fileCount: category.fileCount,
-      };
-      return acc;
-    }, {});
     try {
       const categoryResult = await invoke<CleanupResult>("clean_categories", {
+        operationId: startOperation(),
         request: {
           ids: selectedIds,
           excludedPaths,
           includedPaths,
-          categoryStats,
+          options: categoryOptions,
         },
       });
       let totalDeletedBytes = categoryResult.deletedBytes;
       let totalDeletedCount = categoryResult.deletedCount;
       let failed = [...categoryResult.failed];
+      let cancelled = categoryResult.cancelled;
 
-      if (hadLargeSelection) {
+      if (hadLargeSelection && !cancelled) {
         const largeResult = await invoke<CleanupResult>("clean_large_items", {
+          operationId: startOperation(),
           paths: largeSelectedPaths,
         });
         totalDeletedBytes += largeResult.deletedBytes;
         totalDeletedCount += largeResult.deletedCount;
         failed = [...failed, ...largeResult.failed];
+        cancelled = largeResult.cancelled;
       }
+      setRunningOperation(null);
 
-      const summary = `清理完成，删除 ${totalDeletedCount} 项，释放 ${formatBytes(
+      const summary = `${cancelled ? "清理已取消" : "清理完成"}，删除 ${totalDeletedCount} 项，释放 ${formatBytes(
         totalDeletedBytes,
       )}`;
       setScanStatus(
@@ -536,21 +811,37 @@ function App() {
           ? `${summary}，但有 ${failed.length} 项未能删除`
           : summary,
       );
-      const updated = await invoke<CleanupCategory[]>("scan_cleanup_items");
-      setCategories(updated);
       setSelectedIds([]);
       setIncludedPaths({});
       setIncludedSizes({});
       setLargeSelectedPaths([]);
 
+      // Large items only need the volume walk again when some were deleted.
+      const operationId = startOperation();
       if (hadLargeSelection) {
-        const refreshedLarge = await invoke<LargeItem[]>("scan_large_items");
-        setLargeItems(refreshedLarge);
+        const refreshed = await invoke<FullScan>("scan_full", {
+          operationId,
+          mountPoint: largeVolume,
+          options: categoryOptions,
+        });
+        setCategories(refreshed.categories);
+        setLargeItems(refreshed.largeItems);
+      } else {
+        const refreshed = await invoke<CleanupCategory[]>("scan_cleanup_items", {
+          operationId,
+          options: categoryOptions,
+        });
+        setCategories(refreshed);
       }
+      invoke<BrowserCache[]>("scan_browser_caches")
+        .then(setBrowserCaches)
+        .catch(() => setBrowserCaches([]));
     } catch (err) {
+      if (String(err).includes(CANCELLED_MESSAGE)) return;
       setError(String(err));
       setScanStatus("清理失败，请检查权限后重试");
     } finally {
+      setRunningOperation(null);
       setCleaning(false);
     }
   };
@@ -693,6 +984,10 @@ function App() {
           <div className="progress-meta">
             可用空间 {formatBytes(diskInfo?.freeBytes ?? 0)} / 总容量{" "}
             {formatBytes(diskInfo?.totalBytes ?? 0)}
+            {diskHistory?.daysUntilFull != null &&
+              ` · 按近期增长约 ${Math.ceil(diskHistory.daysUntilFull)} 天后写满（${formatDate(
+                diskHistory.fullAtMs,
+              )}）`}
           </div>
         </div>
       </section>
@@ -702,10 +997,20 @@ function App() {
           <div className="card scan-card">
             <div className="scan-header">
               <div className="scan-title">
-                {scanning ? "正在扫描可清理文件…" : "正在扫描大文件…"}
+                {largeScanning ? "正在扫描磁盘…" : "正在扫描可清理文件…"}
               </div>
-              <div className="scan-percent">
-                {Math.min(99, Math.round(scanProgress))}%
+              <div className="details-header-actions">
+                <div className="scan-percent">
+                  {Math.min(99, Math.round(scanProgress))}%
+                </div>
+                <button
+                  className="ghost-button"
+                  type="button"
+                  onClick={handleStop}
+                  disabled={!runningOperation}
+                >
+                  取消扫描
+                </button>
               </div>
             </div>
             <div className="scan-progress">
@@ -734,14 +1039,25 @@ function App() {
                   <p>扫描常见缓存与临时文件</p>
                 </div>
               </div>
-              <button
-                className="ghost-button"
-                type="button"
-                onClick={handleScan}
-                disabled={scanning}
-              >
-                {scanning ? "扫描中" : "扫描"}
-              </button>
+              <div className="details-header-actions">
+                <label className="inline-toggle">
+                  <input
+                    type="checkbox"
+                    checked={forceRescan}
+                    disabled={scanning}
+                    onChange={(event) => setForceRescan(event.target.checked)}
+                  />
+                  完整重新扫描
+                </label>
+                <button
+                  className="ghost-button"
+                  type="button"
+                  onClick={() => handleScan()}
+                  disabled={scanning}
+                >
+                  {scanning ? "扫描中" : "扫描"}
+                </button>
+              </div>
             </div>
 
             {categories.length === 0 ? (
@@ -799,9 +1115,82 @@ function App() {
                         <div className="item-body">
                           <div className="item-title">{category.title}</div>
                           <div className="item-desc">{category.description}</div>
+                          {category.retentionDays != null && (
+                            <div className="item-meta">
+                              保留最近{" "}
+                              <select
+                                value={category.retentionDays}
+                                disabled={scanning}
+                                onChange={(event) =>
+                                  changeRetention(
+                                    category.id,
+                                    Number(event.target.value),
+                                  )
+                                }
+                              >
+                                {[
+                                  ...new Set([
+                                    ...RETENTION_CHOICES,
+                                    category.retentionDays,
+                                  ]),
+                                ]
+                                  .sort((a, b) => a - b)
+                                  .map((days) => (
+                                    <option key={days} value={days}>
+                                      {days} 天
+                                    </option>
+                                  ))}
+                              </select>{" "}
+                              内修改过的文件
+                            </div>
+                          )}
                           <div className="item-meta">
                             {category.fileCount} 项可清理
+                            {category.inaccessibleCount > 0 &&
+                              ` · 部分扫描，${category.inaccessibleCount} 项无法访问`}
                           </div>
+                          {category.id === "browser_cache" &&
+                            browserCaches.length > 0 && (
+                              <div className="item-meta">
+                                {browserCaches.map((browser) => (
+                                  <div
+                                    key={browser.browser}
+                                    title={browser.profiles
+                                      .map(
+                                        (profile) =>
+                                          `${profile.name} ${formatBytes(
+                                            profile.sizeBytes,
+                                          )}: ${profile.items
+                                            .map(
+                                              (item) =>
+                                                `${item.name} ${formatBytes(
+                                                  item.sizeBytes,
+                                                )}`,
+                                            )
+                                            .join(", ")}`,
+                                      )
+                                      .join("\n")}
+                                  >
+                                    {browser.browser}{" "}
+                                    {formatBytes(browser.sizeBytes)} ·{" "}
+                                    {browser.profiles.length} 个配置文件
+                                  </div>
+                                ))}
+                              </div>
+                            )}
+                          {category.fileCount > 0 && (
+                            <div className="item-meta">
+                              {formatBreakdown(
+                                category.breakdown.extensions,
+                                (key) => key || "无扩展名",
+                              )}
+                              <br />
+                              {formatBreakdown(
+                                category.breakdown.ages,
+                                (key) => AGE_LABELS[key] ?? key,
+                              )}
+                            </div>
+                          )}
                         </div>
                         <div className="item-actions">
                           <button
@@ -813,6 +1202,11 @@ function App() {
                           </button>
                           <div className="item-size">
                             {formatBytes(category.sizeBytes)}
+                            {category.reclaimableBytes < category.sizeBytes && (
+                              <div className="item-meta">
+                                可释放 {formatBytes(category.reclaimableBytes)}
+                              </div>
+                            )}
                           </div>
                         </div>
                       </div>
@@ -837,14 +1231,32 @@ function App() {
                   <p>快速定位占用空间的文件/文件夹</p>
                 </div>
               </div>
-              <button
-                className="ghost-button"
-                type="button"
-                onClick={handleLargeScan}
-                disabled={largeScanning}
-              >
-                {largeScanning ? "扫描中" : "扫描"}
-              </button>
+              <div className="details-header-actions">
+                {disks.length > 1 && (
+                  <select
+                    value={largeVolume ?? ""}
+                    disabled={largeScanning}
+                    onChange={(event) => setLargeVolume(event.target.value || null)}
+                  >
+                    <option value="">系统盘</option>
+                    {disks.map((disk) => (
+                      <option key={disk.mountPoint} value={disk.mountPoint}>
+                        {disk.mountPoint} · {disk.fileSystem}
+                        {disk.removable ? " · 可移动" : ""} ·{" "}
+                        {formatBytes(disk.freeBytes)} 可用
+                      </option>
+                    ))}
+                  </select>
+                )}
+                <button
+                  className="ghost-button"
+                  type="button"
+                  onClick={handleFullScan}
+                  disabled={largeScanning}
+                >
+                  {largeScanning ? "扫描中" : "扫描"}
+                </button>
+              </div>
             </div>
 
             {largeItems.length === 0 ? (
@@ -909,13 +1321,20 @@ function App() {
                           <div className="large-title">
                             <span>{item.name}</span>
                             {item.suspicious && (
-                              <span className="tag">可疑</span>
+                              <span className="tag" title={item.reasons.join("\n")}>
+                                可疑 {item.score}
+                              </span>
                             )}
                           </div>
                           <div className="large-path">{item.path}</div>
                         </div>
                         <div className="large-size">
                           {formatBytes(item.sizeBytes)}
+                          {item.reclaimableBytes < item.sizeBytes && (
+                            <div className="item-meta">
+                              可释放 {formatBytes(item.reclaimableBytes)}
+                            </div>
+                          )}
                         </div>
                       </div>
                     );
@@ -944,10 +1363,10 @@ function App() {
               <button
                 className="secondary-button"
                 type="button"
-                onClick={handleCancel}
-                disabled={cleaning}
+                onClick={cleaning ? handleStop : handleCancel}
+                disabled={cleaning && !runningOperation}
               >
-                取消
+                {cleaning ? "停止清理" : "取消"}
               </button>
               <button
                 className="primary-button"
@@ -975,13 +1394,26 @@ function App() {
                     : `已选择 ${includedCount} 项`}
                 </p>
               </div>
-              <button
-                className="ghost-button"
-                type="button"
-                onClick={closeDetails}
-              >
-                关闭
-              </button>
+              <div className="details-header-actions">
+                <select
+                  value={detailsSort}
+                  onChange={(event) =>
+                    openDetails(activeCategory, event.target.value as ListSort)
+                  }
+                >
+                  <option value="size">按大小</option>
+                  <option value="modified">按修改时间</option>
+                  <option value="name">按名称</option>
+                  <option value="extension">按类型</option>
+                </select>
+                <button
+                  className="ghost-button"
+                  type="button"
+                  onClick={closeDetails}
+                >
+                  关闭
+                </button>
+              </div>
             </div>
 
             {detailsLoading ? (
@@ -1016,7 +1448,11 @@ function App() {
                       <div className="details-info">
                         <div className="details-path">{item.path}</div>
                         <div className="details-meta">
-                          {formatBytes(item.sizeBytes)} · {formatDate(item.modifiedMs)}
+                          {formatBytes(item.sizeBytes)} · 占用{" "}
+                          {formatBytes(item.allocatedBytes)} ·{" "}
+                          {formatDate(item.modifiedMs)}
+                          {item.readOnly && " · 只读"}
+                          {item.hidden && " · 隐藏"}
                         </div>
                       </div>
                       <div className="details-actions">
@@ -1033,7 +1469,15 @@ function App() {
                 })}
                 {detailsHasMore && (
                   <div className="details-more">
-                    仅显示部分文件，更多内容请在资源管理器中查看。
+                    已显示 {detailItems.length} / {detailsTotal} 个文件 ·{" "}
+                    <button
+                      className="link-button"
+                      type="button"
+                      onClick={loadMoreDetails}
+                      disabled={detailsLoadingMore}
+                    >
+                      {detailsLoadingMore ? "加载中..." : "加载更多"}
+                    </button>
                   </div>
                 )}
               </div>